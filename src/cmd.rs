@@ -1,3 +1,8 @@
 pub mod display;
+pub mod expr;
+pub mod gitignore;
+pub mod grep;
+pub mod render;
 pub mod root;
 pub mod traversal;
+pub mod users;
@@ -1,12 +1,12 @@
 use std::{
-    fs::{DirEntry, FileType},
+    fs::{DirEntry, FileType, Metadata},
     io::Result,
     os::unix::fs::PermissionsExt,
     path::Path,
     time::{SystemTime, UNIX_EPOCH},
 };
 
-use crate::cmd::root::Opts;
+use crate::cmd::{grep::GrepMatch, root::Opts};
 
 use ansi_term::Colour::Red;
 
@@ -114,6 +114,13 @@ pub fn format_date(time: SystemTime) -> String {
     }
 }
 
+pub fn format_iso_date(time: SystemTime) -> String {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(_) => format!("{}Z", format_date(time).replacen(' ', "T", 1)),
+        Err(_) => String::new(),
+    }
+}
+
 pub fn format_icon(path: &Path, file_type: FileType) -> &str {
     if file_type.is_dir() {
         return " ";
@@ -135,15 +142,25 @@ pub fn format_icon(path: &Path, file_type: FileType) -> &str {
     "󰈔 "
 }
 
-pub fn format_entry_line(
+/// How a part of an entry line is painted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Paint {
+    Plain,
+    Red,
+    RedBold,
+}
+
+pub fn format_entry_segments(
     entry: &DirEntry,
+    name: &str,
     opts: &Opts,
     indent_state: &[bool],
     is_last: bool,
     highlight: bool,
     first_ancestor_matched: usize,
-) -> Result<String> {
+) -> Result<Vec<(String, Paint)>> {
     let path = entry.path();
+    let mut segments = Vec::new();
     let mut line = String::new();
     let metadata = entry.metadata()?;
     let file_type = metadata.file_type();
@@ -178,7 +195,8 @@ pub fn format_entry_line(
             } else {
                 let vertical_line = if opts.ascii { "|   " } else { "│   " };
                 if first_ancestor_matched < indent_level {
-                    line.push_str(&Red.paint(vertical_line).to_string());
+                    segments.push((std::mem::take(&mut line), Paint::Plain));
+                    segments.push((vertical_line.to_string(), Paint::Red));
                 } else {
                     line.push_str(vertical_line);
                 }
@@ -195,7 +213,8 @@ pub fn format_entry_line(
     };
 
     if first_ancestor_matched < indent_state.len() {
-        line.push_str(&Red.paint(line_prefix).to_string());
+        segments.push((std::mem::take(&mut line), Paint::Plain));
+        segments.push((line_prefix.to_string(), Paint::Red));
     } else {
         line.push_str(line_prefix);
     }
@@ -205,14 +224,11 @@ pub fn format_entry_line(
         display_path.push_str(format_icon(&path, file_type));
     }
 
-    if opts.full_path {
-        display_path.push_str(&path.display().to_string());
-    } else {
-        display_path.push_str(entry.file_name().to_string_lossy().as_ref());
-    };
+    display_path.push_str(name);
 
     if highlight {
-        line.push_str(&Red.bold().paint(display_path).to_string());
+        segments.push((std::mem::take(&mut line), Paint::Plain));
+        segments.push((display_path, Paint::RedBold));
     } else {
         line.push_str(&display_path);
     }
@@ -223,5 +239,152 @@ pub fn format_entry_line(
         line.push_str(&size_str);
     }
 
+    segments.push((line, Paint::Plain));
+    segments.retain(|(text, _)| !text.is_empty());
+    Ok(segments)
+}
+
+pub fn format_entry_line(
+    entry: &DirEntry,
+    name: &str,
+    opts: &Opts,
+    indent_state: &[bool],
+    is_last: bool,
+    highlight: bool,
+    first_ancestor_matched: usize,
+) -> Result<String> {
+    let segments = format_entry_segments(
+        entry,
+        name,
+        opts,
+        indent_state,
+        is_last,
+        highlight,
+        first_ancestor_matched,
+    )?;
+
+    let mut line = String::new();
+    for (text, paint) in segments {
+        match paint {
+            Paint::Plain => line.push_str(&text),
+            Paint::Red => line.push_str(&Red.paint(text).to_string()),
+            Paint::RedBold => line.push_str(&Red.bold().paint(text).to_string()),
+        }
+    }
     Ok(line)
 }
+
+pub fn format_root_line(path: &Path, metadata: &Metadata, opts: &Opts) -> Result<String> {
+    let mut display_path = String::new();
+
+    if opts.print_permissions {
+        let mode = metadata.permissions().mode();
+        let perms_str = format_permissions(mode, metadata.file_type().is_dir());
+        display_path.push_str(&perms_str);
+        display_path.push(' ');
+    }
+
+    if opts.last_modify {
+        match metadata.modified() {
+            Ok(mod_time) => {
+                let date_str = format!("[{}] ", format_date(mod_time));
+                display_path.push_str(&date_str);
+            }
+            Err(e) => {
+                eprintln!(
+                    "Warning: Could not get modification date for {:?}: {}",
+                    path, e
+                );
+            }
+        }
+    }
+
+    if opts.full_path {
+        display_path.push_str(&path.canonicalize()?.display().to_string());
+    } else {
+        display_path.push_str(
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or("."),
+        );
+    };
+
+    Ok(display_path)
+}
+
+/// Annotation of a file listed by --grep: match count and first matching line.
+pub fn format_grep_match(grep_match: &GrepMatch) -> String {
+    let match_str = if grep_match.count == 1 {
+        "match"
+    } else {
+        "matches"
+    };
+    format!(
+        "{} {}, line {}: {}",
+        grep_match.count, match_str, grep_match.first_line_number, grep_match.first_line
+    )
+}
+
+/// Annotation of a directory not opened because of --filelimit, as GNU tree prints it.
+pub fn format_filelimit(count: usize) -> String {
+    format!("{count} entries exceeds filelimit, not opening dir")
+}
+
+pub fn format_report(stats: (u64, u64)) -> String {
+    let dir_str = if stats.0 == 1 {
+        "directory"
+    } else {
+        "directories"
+    };
+    let file_str = if stats.1 == 1 { "file" } else { "files" };
+    format!("{} {}, {} {}", stats.0, dir_str, stats.1, file_str)
+}
+
+pub fn escape_json(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+pub fn escape_xml(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\n' => escaped.push_str("&#10;"),
+            '\r' => escaped.push_str("&#13;"),
+            '\t' => escaped.push_str("&#9;"),
+            // Other control characters are not allowed in XML 1.0 at all
+            c if (c as u32) < 0x20 => escaped.push('\u{fffd}'),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+pub fn encode_url_path(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
+}
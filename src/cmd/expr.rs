@@ -0,0 +1,414 @@
+use std::{fs::Metadata, time::SystemTime};
+
+use glob::{MatchOptions, Pattern};
+
+use crate::cmd::{
+    render::type_name,
+    root::{parse_size, parse_time},
+};
+
+const FIELDS: &str = "name, ext, path, type, size, depth or mtime";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Match,
+    NotMatch,
+}
+
+impl Op {
+    fn as_str(self) -> &'static str {
+        match self {
+            Op::Eq => "==",
+            Op::Ne => "!=",
+            Op::Lt => "<",
+            Op::Le => "<=",
+            Op::Gt => ">",
+            Op::Ge => ">=",
+            Op::Match => "~",
+            Op::NotMatch => "!~",
+        }
+    }
+
+    fn compare<T: PartialOrd>(self, left: T, right: T) -> bool {
+        match self {
+            Op::Eq => left == right,
+            Op::Ne => left != right,
+            Op::Lt => left < right,
+            Op::Le => left <= right,
+            Op::Gt => left > right,
+            Op::Ge => left >= right,
+            Op::Match | Op::NotMatch => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Str(String),
+    Op(Op),
+    And,
+    Or,
+    Not,
+    LParen,
+    RParen,
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Word(word) => format!("`{word}`"),
+            Token::Str(s) => format!("\"{s}\""),
+            Token::Op(op) => format!("`{}`", op.as_str()),
+            Token::And => String::from("`and`"),
+            Token::Or => String::from("`or`"),
+            Token::Not => String::from("`not`"),
+            Token::LParen => String::from("`(`"),
+            Token::RParen => String::from("`)`"),
+        }
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    !c.is_whitespace() && !"()\"'<>=!~&|".contains(c)
+}
+
+/// Splits an expression into tokens, each with its (1-based) column.
+fn tokenize(input: &str) -> Result<Vec<(Token, usize)>, String> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let column = i + 1;
+        let next = chars.get(i + 1).copied();
+
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        let (token, len) = match (c, next) {
+            ('(', _) => (Token::LParen, 1),
+            (')', _) => (Token::RParen, 1),
+            ('=', Some('=')) => (Token::Op(Op::Eq), 2),
+            ('=', _) => (Token::Op(Op::Eq), 1),
+            ('!', Some('=')) => (Token::Op(Op::Ne), 2),
+            ('!', Some('~')) => (Token::Op(Op::NotMatch), 2),
+            ('!', _) => (Token::Not, 1),
+            ('<', Some('=')) => (Token::Op(Op::Le), 2),
+            ('<', _) => (Token::Op(Op::Lt), 1),
+            ('>', Some('=')) => (Token::Op(Op::Ge), 2),
+            ('>', _) => (Token::Op(Op::Gt), 1),
+            ('~', _) => (Token::Op(Op::Match), 1),
+            ('&', Some('&')) => (Token::And, 2),
+            ('|', Some('|')) => (Token::Or, 2),
+            ('"' | '\'', _) => {
+                let mut s = String::new();
+                let mut end = i + 1;
+                loop {
+                    match chars.get(end) {
+                        None => return Err(format!("unterminated string at column {column}")),
+                        Some('\\') if end + 1 < chars.len() => {
+                            s.push(chars[end + 1]);
+                            end += 2;
+                        }
+                        Some(&quote) if quote == c => break,
+                        Some(&other) => {
+                            s.push(other);
+                            end += 1;
+                        }
+                    }
+                }
+                (Token::Str(s), end + 1 - i)
+            }
+            (c, _) if is_word_char(c) => {
+                let word: String = chars[i..]
+                    .iter()
+                    .take_while(|&&c| is_word_char(c))
+                    .collect();
+                let len = word.chars().count();
+                let token = match word.to_ascii_lowercase().as_str() {
+                    "and" => Token::And,
+                    "or" => Token::Or,
+                    "not" => Token::Not,
+                    _ => Token::Word(word),
+                };
+                (token, len)
+            }
+            (c, _) => return Err(format!("unexpected `{c}` at column {column}")),
+        };
+
+        tokens.push((token, column));
+        i += len;
+    }
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Name,
+    Ext,
+    Path,
+    Type,
+    Size,
+    Depth,
+    Mtime,
+}
+
+#[derive(Debug)]
+enum Value {
+    Str(String),
+    Glob(Pattern),
+    Number(u64),
+    Time(SystemTime),
+}
+
+#[derive(Debug)]
+pub struct Comparison {
+    field: Field,
+    op: Op,
+    value: Value,
+}
+
+/// A compiled --where expression.
+#[derive(Debug)]
+pub enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Compare(Comparison),
+}
+
+/// What an expression is evaluated against.
+pub struct Subject<'a> {
+    pub name: &'a str,
+    // Path relative to the root of the tree
+    pub path: &'a str,
+    pub metadata: &'a Metadata,
+    // Children of the root have depth 1
+    pub depth: usize,
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    position: usize,
+    end_column: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(token, _)| token)
+    }
+
+    fn column(&self) -> usize {
+        self.tokens
+            .get(self.position)
+            .map_or(self.end_column, |(_, column)| *column)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self
+            .tokens
+            .get(self.position)
+            .map(|(token, _)| token.clone());
+        self.position += 1;
+        token
+    }
+
+    fn unexpected(&self, expected: &str) -> String {
+        match self.peek() {
+            Some(token) => format!(
+                "expected {expected} at column {}, found {}",
+                self.column(),
+                token.describe()
+            ),
+            None => format!("expected {expected} at the end of the expression"),
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, String> {
+        let mut expr = self.parse_and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.next();
+            expr = Expr::Or(Box::new(expr), Box::new(self.parse_and()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, String> {
+        let mut expr = self.parse_not()?;
+        while self.peek() == Some(&Token::And) {
+            self.next();
+            expr = Expr::And(Box::new(expr), Box::new(self.parse_not()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_not(&mut self) -> Result<Expr, String> {
+        if self.peek() == Some(&Token::Not) {
+            self.next();
+            return Ok(Expr::Not(Box::new(self.parse_not()?)));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, String> {
+        if self.peek() == Some(&Token::LParen) {
+            self.next();
+            let expr = self.parse_or()?;
+            if self.peek() != Some(&Token::RParen) {
+                return Err(self.unexpected("`)`"));
+            }
+            self.next();
+            return Ok(expr);
+        }
+        self.parse_comparison()
+    }
+
+    fn parse_comparison(&mut self) -> Result<Expr, String> {
+        let field = match self.peek() {
+            Some(Token::Word(word)) => match word.as_str() {
+                "name" => Field::Name,
+                "ext" => Field::Ext,
+                "path" => Field::Path,
+                "type" => Field::Type,
+                "size" => Field::Size,
+                "depth" => Field::Depth,
+                "mtime" => Field::Mtime,
+                _ => return Err(self.unexpected(&format!("a field ({FIELDS})"))),
+            },
+            _ => return Err(self.unexpected(&format!("a field ({FIELDS})"))),
+        };
+        let field_name = self
+            .next()
+            .map(|token| token.describe())
+            .unwrap_or_default();
+
+        let op_column = self.column();
+        let op = match self.peek() {
+            Some(Token::Op(op)) => *op,
+            _ => return Err(self.unexpected("a comparison operator")),
+        };
+        self.next();
+
+        let value_column = self.column();
+        let value = match self.peek() {
+            Some(Token::Word(value) | Token::Str(value)) => value.clone(),
+            _ => return Err(self.unexpected("a value")),
+        };
+        self.next();
+
+        let value = match (field, op) {
+            (Field::Name | Field::Ext | Field::Path | Field::Type, Op::Match | Op::NotMatch) => {
+                Value::Glob(
+                    Pattern::new(&value).map_err(|err| {
+                        format!("invalid pattern at column {value_column}: {err}")
+                    })?,
+                )
+            }
+            (Field::Name | Field::Ext | Field::Path, Op::Eq | Op::Ne) => Value::Str(value),
+            (Field::Type, Op::Eq | Op::Ne) => match value.as_str() {
+                "file" | "directory" | "link" => Value::Str(value),
+                _ => {
+                    return Err(format!(
+                        "expected a type (file, directory or link) at column {value_column}"
+                    ));
+                }
+            },
+            (Field::Size, _) if !matches!(op, Op::Match | Op::NotMatch) => Value::Number(
+                parse_size(&value).map_err(|err| format!("{err} at column {value_column}"))?,
+            ),
+            (Field::Depth, _) if !matches!(op, Op::Match | Op::NotMatch) => Value::Number(
+                value
+                    .parse()
+                    .map_err(|_| format!("invalid depth `{value}` at column {value_column}"))?,
+            ),
+            (Field::Mtime, _) if !matches!(op, Op::Match | Op::NotMatch) => Value::Time(
+                parse_time(&value).map_err(|err| format!("{err} at column {value_column}"))?,
+            ),
+            _ => {
+                return Err(format!(
+                    "`{}` can't be used with {field_name} at column {op_column}",
+                    op.as_str()
+                ));
+            }
+        };
+
+        Ok(Expr::Compare(Comparison { field, op, value }))
+    }
+}
+
+impl Expr {
+    /// Compiles an expression such as `ext == "rs" and size > 10k and not path ~ "tests/**"`.
+    pub fn parse(input: &str) -> Result<Expr, String> {
+        let tokens = tokenize(input)?;
+        if tokens.is_empty() {
+            return Err(String::from("empty expression"));
+        }
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+            end_column: input.chars().count() + 1,
+        };
+        let expr = parser.parse_or()?;
+        if parser.peek().is_some() {
+            return Err(parser.unexpected("`and`, `or` or the end of the expression"));
+        }
+        Ok(expr)
+    }
+
+    pub fn matches(&self, subject: &Subject, match_options: MatchOptions) -> bool {
+        match self {
+            Expr::And(left, right) => {
+                left.matches(subject, match_options) && right.matches(subject, match_options)
+            }
+            Expr::Or(left, right) => {
+                left.matches(subject, match_options) || right.matches(subject, match_options)
+            }
+            Expr::Not(expr) => !expr.matches(subject, match_options),
+            Expr::Compare(comparison) => comparison.matches(subject, match_options),
+        }
+    }
+}
+
+impl Comparison {
+    fn matches(&self, subject: &Subject, match_options: MatchOptions) -> bool {
+        let text = match self.field {
+            Field::Name => subject.name,
+            Field::Path => subject.path,
+            Field::Ext => subject
+                .name
+                .rsplit_once('.')
+                .filter(|(stem, _)| !stem.is_empty())
+                .map_or("", |(_, ext)| ext),
+            Field::Type => type_name(subject.metadata.file_type()),
+            Field::Size | Field::Depth | Field::Mtime => "",
+        };
+
+        match &self.value {
+            Value::Str(value) => self.op.compare(text, value.as_str()),
+            Value::Glob(pattern) => {
+                pattern.matches_with(text, match_options) == (self.op == Op::Match)
+            }
+            Value::Number(value) => {
+                let number = match self.field {
+                    Field::Size => subject.metadata.len(),
+                    _ => subject.depth as u64,
+                };
+                self.op.compare(number, *value)
+            }
+            Value::Time(value) => subject
+                .metadata
+                .modified()
+                .is_ok_and(|mtime| self.op.compare(mtime, *value)),
+        }
+    }
+}
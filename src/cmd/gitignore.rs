@@ -0,0 +1,221 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    env,
+    fs::read_to_string,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use glob::{MatchOptions, Pattern};
+
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+/// Ignore files read in every directory, later ones take precedence.
+const IGNORE_FILES: [&str; 2] = [".gitignore", ".ignore"];
+
+struct Rule {
+    pattern: Pattern,
+    negated: bool,
+    dir_only: bool,
+    // Patterns with a slash are relative to the directory of their ignore file,
+    // the others match the name of the entry at any depth
+    anchored: bool,
+    base: PathBuf,
+}
+
+impl Rule {
+    fn parse(line: &str, base: &Path) -> Option<Rule> {
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+
+        let (negated, line) = match line.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, line.strip_prefix('\\').unwrap_or(line)),
+        };
+        let (dir_only, line) = match line.strip_suffix('/') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        let anchored = line.contains('/');
+        let line = line.strip_prefix('/').unwrap_or(line);
+
+        let pattern = Pattern::new(line).ok()?;
+        Some(Rule {
+            pattern,
+            negated,
+            dir_only,
+            anchored,
+            base: base.to_path_buf(),
+        })
+    }
+
+    fn matches(&self, path: &Path, is_dir: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+        if self.anchored {
+            path.strip_prefix(&self.base)
+                .ok()
+                .and_then(|relative| relative.to_str())
+                .is_some_and(|relative| self.pattern.matches_with(relative, MATCH_OPTIONS))
+        } else {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| self.pattern.matches_with(name, MATCH_OPTIONS))
+        }
+    }
+}
+
+fn parse_rules(content: &str, base: &Path) -> Vec<Rule> {
+    content
+        .lines()
+        .filter_map(|line| Rule::parse(line, base))
+        .collect()
+}
+
+fn home_dir() -> Option<PathBuf> {
+    env::var_os("HOME").map(PathBuf::from)
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Path of the user's global excludes file: `core.excludesFile` from the git config,
+/// falling back to `$XDG_CONFIG_HOME/git/ignore`.
+fn global_excludes_file() -> Option<PathBuf> {
+    let config_dir = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| home_dir().map(|home| home.join(".config")));
+
+    let config_files = [
+        home_dir().map(|home| home.join(".gitconfig")),
+        config_dir.as_ref().map(|dir| dir.join("git/config")),
+    ];
+    for config_file in config_files.into_iter().flatten() {
+        let Ok(content) = read_to_string(config_file) else {
+            continue;
+        };
+        let mut in_core = false;
+        for line in content.lines().map(str::trim) {
+            if line.starts_with('[') {
+                in_core = line.eq_ignore_ascii_case("[core]");
+            } else if in_core
+                && let Some((key, value)) = line.split_once('=')
+                && key.trim().eq_ignore_ascii_case("excludesfile")
+            {
+                return Some(expand_home(value.trim().trim_matches('"')));
+            }
+        }
+    }
+
+    config_dir.map(|dir| dir.join("git/ignore"))
+}
+
+/// Decides whether paths are ignored by the git ignore rules that apply to them.
+///
+/// Rules are read lazily from every directory between the top of the repository
+/// (or the root of the tree outside of a repository) and the entry, so that a
+/// nested ignore file can override its parents.
+pub struct Gitignore {
+    // Paths are visited relative to the root as given, rules are matched on the canonical one
+    root: PathBuf,
+    canonical_root: PathBuf,
+    top: PathBuf,
+    global_rules: Vec<Rule>,
+    dir_rules: RefCell<HashMap<PathBuf, Rc<Vec<Rule>>>>,
+}
+
+impl Gitignore {
+    pub fn new(root: &Path) -> Self {
+        let canonical_root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+        let repo = canonical_root
+            .ancestors()
+            .find(|dir| dir.join(".git").exists())
+            .map(Path::to_path_buf);
+        let top = repo.clone().unwrap_or_else(|| canonical_root.clone());
+
+        let mut global_rules = Vec::new();
+        if let Some(file) = global_excludes_file()
+            && let Ok(content) = read_to_string(file)
+        {
+            global_rules.extend(parse_rules(&content, &top));
+        }
+        if let Some(repo) = &repo
+            && let Ok(content) = read_to_string(repo.join(".git/info/exclude"))
+        {
+            global_rules.extend(parse_rules(&content, &top));
+        }
+
+        Self {
+            root: root.to_path_buf(),
+            canonical_root,
+            top,
+            global_rules,
+            dir_rules: RefCell::new(HashMap::new()),
+        }
+    }
+
+    fn rules_in(&self, dir: &Path) -> Rc<Vec<Rule>> {
+        if let Some(rules) = self.dir_rules.borrow().get(dir) {
+            return Rc::clone(rules);
+        }
+        let rules: Vec<Rule> = IGNORE_FILES
+            .iter()
+            .filter_map(|file| read_to_string(dir.join(file)).ok())
+            .flat_map(|content| parse_rules(&content, dir))
+            .collect();
+        let rules = Rc::new(rules);
+        self.dir_rules
+            .borrow_mut()
+            .insert(dir.to_path_buf(), Rc::clone(&rules));
+        rules
+    }
+
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let Ok(relative) = path.strip_prefix(&self.root) else {
+            return false;
+        };
+        let path = self.canonical_root.join(relative);
+        if path.file_name().is_some_and(|name| name == ".git") {
+            return true;
+        }
+        let Some(parent) = path.parent() else {
+            return false;
+        };
+        let Ok(relative_parent) = parent.strip_prefix(&self.top) else {
+            return false;
+        };
+
+        // Rules apply from the top directory down to the parent of the entry,
+        // the last one that matches wins
+        let mut dirs: Vec<PathBuf> = relative_parent
+            .ancestors()
+            .map(|relative| self.top.join(relative))
+            .collect();
+        dirs.reverse();
+
+        let mut ignored = false;
+        let dir_rules: Vec<Rc<Vec<Rule>>> = dirs.iter().map(|dir| self.rules_in(dir)).collect();
+        let all_rules = self
+            .global_rules
+            .iter()
+            .chain(dir_rules.iter().flat_map(|rules| rules.iter()));
+        for rule in all_rules {
+            if rule.matches(&path, is_dir) {
+                ignored = !rule.negated;
+            }
+        }
+        ignored
+    }
+}
@@ -0,0 +1,66 @@
+use std::{
+    fs::File,
+    io::{Read, Result},
+    path::Path,
+};
+
+use regex::Regex;
+
+/// Files with a NUL byte in their first block are considered binary, like grep does.
+const BINARY_CHECK_LEN: usize = 8192;
+
+/// Matched lines longer than this are cut, so minified files don't flood the output.
+const FIRST_LINE_WIDTH: usize = 80;
+
+/// Lines of a file matched by --grep.
+pub struct GrepMatch {
+    pub count: usize,
+    pub first_line_number: usize,
+    pub first_line: String,
+}
+
+/// Reads a file, stopping after its first block if it is binary and those are skipped.
+fn read_text(path: &Path, binary: bool) -> Result<Option<Vec<u8>>> {
+    let mut file = File::open(path)?;
+    let mut content = Vec::new();
+    file.by_ref()
+        .take(BINARY_CHECK_LEN as u64)
+        .read_to_end(&mut content)?;
+    if !binary && content.contains(&0) {
+        return Ok(None);
+    }
+    file.read_to_end(&mut content)?;
+    Ok(Some(content))
+}
+
+/// Makes a line from a file safe to print: control characters (except tabs) could
+/// be terminal escape sequences, so they are replaced, and long lines are cut.
+fn sanitize_line(line: &str) -> String {
+    let mut chars = line
+        .chars()
+        .map(|c| if c.is_control() && c != '\t' { '?' } else { c });
+    let mut sanitized: String = chars.by_ref().take(FIRST_LINE_WIDTH).collect();
+    if chars.next().is_some() {
+        sanitized.push_str("...");
+    }
+    sanitized
+}
+
+/// Searches the lines of a file, returns None if nothing matched or if the file
+/// could not be read or is binary (unless `binary` is set).
+pub fn search_file(path: &Path, regex: &Regex, binary: bool) -> Option<GrepMatch> {
+    let content = read_text(path, binary).ok()??;
+
+    let content = String::from_utf8_lossy(&content);
+    let mut matched_lines = content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| regex.is_match(line));
+    let (index, first_line) = matched_lines.next()?;
+
+    Some(GrepMatch {
+        count: matched_lines.count() + 1,
+        first_line_number: index + 1,
+        first_line: sanitize_line(first_line.trim()),
+    })
+}
@@ -0,0 +1,128 @@
+use std::{
+    fs::{DirEntry, FileType, Metadata},
+    io::{Result, Write},
+    path::Path,
+};
+
+use crate::cmd::{
+    display::encode_url_path,
+    grep::GrepMatch,
+    render::{
+        dot::DotRender, html::HtmlRender, json::JsonRender, markdown::MarkdownRender,
+        mermaid::MermaidRender, ndjson::NdjsonRender, print0::Print0Render, svg::SvgRender,
+        table::TableRender, text::TextRender, xml::XmlRender, yaml::YamlRender,
+    },
+    root::{Opts, OutputFormat},
+};
+
+pub mod dot;
+pub mod html;
+pub mod json;
+pub mod markdown;
+pub mod mermaid;
+pub mod ndjson;
+pub mod print0;
+pub mod svg;
+pub mod table;
+pub mod text;
+pub mod xml;
+pub mod yaml;
+
+/// A displayed entry, as seen by a renderer.
+pub struct Entry<'a> {
+    pub dir_entry: &'a DirEntry,
+    pub depth: usize,
+    // Indentation level, which is the depth unless shallower levels are collapsed by --min-depth
+    pub level: usize,
+    pub indent_state: &'a [bool],
+    pub is_last: bool,
+    pub matched: bool,
+    pub highlight: bool,
+    pub first_matched_ancestor: usize,
+    pub grep_match: Option<&'a GrepMatch>,
+    // Number of entries of a directory not opened because of --filelimit
+    pub filelimit: Option<usize>,
+    // Collapsed ancestors of an entry of the first displayed level with --min-depth
+    pub prefix: Option<&'a Path>,
+}
+
+impl Entry<'_> {
+    /// Name of the entry (after its collapsed ancestors with --min-depth), or its full path with -f.
+    pub fn name(&self, opts: &Opts) -> String {
+        if opts.full_path {
+            self.dir_entry.path().display().to_string()
+        } else if let Some(prefix) = self.prefix {
+            prefix
+                .join(self.dir_entry.file_name())
+                .display()
+                .to_string()
+        } else {
+            self.dir_entry.file_name().to_string_lossy().to_string()
+        }
+    }
+}
+
+/// Name of the root directory as given on the command line, or its canonical path with -f.
+pub fn root_name(path: &Path, opts: &Opts) -> Result<String> {
+    if opts.full_path {
+        Ok(path.canonicalize()?.display().to_string())
+    } else {
+        Ok(path.display().to_string())
+    }
+}
+
+/// Link to an entry for --base-href, made of its URL-encoded path relative to the root.
+pub fn entry_href(base_href: &str, root: &Path, path: &Path) -> String {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    let href = format!(
+        "{}/{}",
+        base_href.trim_end_matches('/'),
+        encode_url_path(&relative.to_string_lossy())
+    );
+    href.trim_end_matches('/').to_string()
+}
+
+/// Type of an entry as named in the structured outputs.
+pub fn type_name(file_type: FileType) -> &'static str {
+    if file_type.is_dir() {
+        "directory"
+    } else if file_type.is_symlink() {
+        "link"
+    } else {
+        "file"
+    }
+}
+
+pub trait Render {
+    /// Called once for the root directory, before any entry.
+    fn begin(&mut self, path: &Path, metadata: &Metadata) -> Result<()>;
+
+    /// Called for every displayed entry, before descending into it.
+    fn entry(&mut self, entry: &Entry) -> Result<()>;
+
+    /// Called once all the children of a directory entry have been rendered.
+    fn leave_dir(&mut self, _entry: &Entry) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called once after the traversal with the (dirs, files) counts.
+    fn end(&mut self, stats: (u64, u64)) -> Result<()>;
+}
+
+pub fn new_renderer<'a>(opts: &'a Opts, writer: &'a mut dyn Write) -> Box<dyn Render + 'a> {
+    match opts.output_format {
+        OutputFormat::Text => Box::new(TextRender::new(opts, writer)),
+        OutputFormat::Json => Box::new(JsonRender::new(opts, writer)),
+        OutputFormat::Xml => Box::new(XmlRender::new(opts, writer)),
+        OutputFormat::Html => Box::new(HtmlRender::new(opts, writer)),
+        OutputFormat::Ndjson => Box::new(NdjsonRender::new(writer)),
+        OutputFormat::Dot => Box::new(DotRender::new(opts, writer)),
+        OutputFormat::Markdown => Box::new(MarkdownRender::new(opts, writer)),
+        OutputFormat::Mermaid => Box::new(MermaidRender::new(opts, writer)),
+        OutputFormat::Csv => Box::new(TableRender::csv(writer)),
+        OutputFormat::Tsv => Box::new(TableRender::tsv(writer)),
+        OutputFormat::Print0 => Box::new(Print0Render::new(writer)),
+        OutputFormat::Svg => Box::new(SvgRender::new(opts, writer)),
+        OutputFormat::Yaml => Box::new(YamlRender::new(opts, writer)),
+    }
+}
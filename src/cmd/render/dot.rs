@@ -0,0 +1,93 @@
+use std::{
+    fs::Metadata,
+    io::{Result, Write},
+    path::Path,
+};
+
+use crate::cmd::{
+    display::{escape_json, format_file_size},
+    render::{Entry, Render, root_name},
+    root::Opts,
+};
+
+const HIGHLIGHT_COLOR: &str = "#f4a3a3";
+
+/// Renders the tree as a Graphviz digraph.
+pub struct DotRender<'a> {
+    opts: &'a Opts,
+    writer: &'a mut dyn Write,
+    next_id: usize,
+    parents: Vec<usize>,
+}
+
+impl<'a> DotRender<'a> {
+    pub fn new(opts: &'a Opts, writer: &'a mut dyn Write) -> Self {
+        Self {
+            opts,
+            writer,
+            next_id: 0,
+            parents: Vec::new(),
+        }
+    }
+
+    fn write_node(&mut self, label: &str, metadata: &Metadata, matched: bool) -> Result<usize> {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let is_dir = metadata.file_type().is_dir();
+        let mut label = label.to_string();
+        if self.opts.print_size && !is_dir {
+            label.push_str(&format!("\n({})", format_file_size(metadata.len())));
+        }
+        let shape = if is_dir { "folder" } else { "note" };
+        let fill = if matched {
+            format!(", style=filled, fillcolor=\"{HIGHLIGHT_COLOR}\"")
+        } else {
+            String::new()
+        };
+
+        // DOT strings use the same escaping rules as JSON for quotes, backslashes and newlines
+        writeln!(
+            self.writer,
+            "  n{id} [label=\"{}\", shape={shape}{fill}];",
+            escape_json(&label)
+        )?;
+        if let Some(parent) = self.parents.last() {
+            writeln!(self.writer, "  n{parent} -> n{id};")?;
+        }
+        Ok(id)
+    }
+}
+
+impl Render for DotRender<'_> {
+    fn begin(&mut self, path: &Path, metadata: &Metadata) -> Result<()> {
+        let name = root_name(path, self.opts)?;
+        writeln!(
+            self.writer,
+            "digraph tree {{\n  rankdir=LR;\n  node [fontname=\"monospace\"];"
+        )?;
+        let id = self.write_node(&name, metadata, false)?;
+        self.parents.push(id);
+        Ok(())
+    }
+
+    fn entry(&mut self, entry: &Entry) -> Result<()> {
+        let metadata = entry.dir_entry.metadata()?;
+        let name = entry.name(self.opts);
+
+        let id = self.write_node(&name, &metadata, entry.matched)?;
+        if metadata.file_type().is_dir() {
+            self.parents.push(id);
+        }
+        Ok(())
+    }
+
+    fn leave_dir(&mut self, _entry: &Entry) -> Result<()> {
+        self.parents.pop();
+        Ok(())
+    }
+
+    fn end(&mut self, _stats: (u64, u64)) -> Result<()> {
+        writeln!(self.writer, "}}")
+    }
+}
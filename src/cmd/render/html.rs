@@ -0,0 +1,145 @@
+use std::{
+    fs::Metadata,
+    io::{Result, Write},
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+};
+
+use crate::cmd::{
+    display::{escape_xml, format_date, format_file_size, format_permissions, format_report},
+    render::{Entry, Render, entry_href, root_name},
+    root::Opts,
+};
+
+const STYLE: &str = "body { font-family: monospace; }
+details > .contents { margin-left: 0.6em; padding-left: 1.2em; border-left: 1px solid #ccc; }
+details > .contents.in-match { border-left-color: #c00; }
+summary, .entry { display: flex; gap: 1.5em; }
+summary { cursor: pointer; }
+.name { flex: 1; }
+.perm, .size, .date { white-space: pre; color: #666; }
+.size { min-width: 7em; text-align: right; }
+.dir > summary .name { font-weight: bold; }
+.matched > .name, .matched > summary .name { color: #c00; font-weight: bold; }
+a { color: inherit; }
+.report { margin-top: 1em; }";
+
+/// Renders the tree as a single HTML page, where each directory can be collapsed.
+pub struct HtmlRender<'a> {
+    opts: &'a Opts,
+    writer: &'a mut dyn Write,
+    root: PathBuf,
+}
+
+impl<'a> HtmlRender<'a> {
+    pub fn new(opts: &'a Opts, writer: &'a mut dyn Write) -> Self {
+        Self {
+            opts,
+            writer,
+            root: PathBuf::new(),
+        }
+    }
+
+    fn format_name(&self, path: &Path, name: &str) -> String {
+        let name = escape_xml(name);
+        match &self.opts.base_href {
+            Some(base_href) => format!(
+                "<a href=\"{}\">{name}</a>",
+                escape_xml(&entry_href(base_href, &self.root, path))
+            ),
+            None => name,
+        }
+    }
+
+    fn format_columns(&self, metadata: &Metadata) -> String {
+        let mut columns = String::new();
+
+        if self.opts.print_permissions {
+            let mode = metadata.permissions().mode();
+            let perms_str = format_permissions(mode, metadata.file_type().is_dir());
+            columns.push_str(&format!("<span class=\"perm\">{perms_str}</span>"));
+        }
+
+        if self.opts.print_size {
+            let size_str = if metadata.file_type().is_dir() {
+                String::new()
+            } else {
+                format_file_size(metadata.len())
+            };
+            columns.push_str(&format!("<span class=\"size\">{size_str}</span>"));
+        }
+
+        if self.opts.last_modify
+            && let Ok(mod_time) = metadata.modified()
+        {
+            columns.push_str(&format!(
+                "<span class=\"date\">{}</span>",
+                format_date(mod_time)
+            ));
+        }
+
+        columns
+    }
+}
+
+impl Render for HtmlRender<'_> {
+    fn begin(&mut self, path: &Path, metadata: &Metadata) -> Result<()> {
+        self.root = path.to_path_buf();
+        let name = root_name(path, self.opts)?;
+
+        writeln!(
+            self.writer,
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n{STYLE}\n</style>\n</head>\n<body>",
+            escape_xml(&name)
+        )?;
+        writeln!(
+            self.writer,
+            "<details class=\"dir\" open><summary><span class=\"name\">{}</span>{}</summary>\n<div class=\"contents\">",
+            self.format_name(path, &name),
+            self.format_columns(metadata)
+        )
+    }
+
+    fn entry(&mut self, entry: &Entry) -> Result<()> {
+        let path = entry.dir_entry.path();
+        let metadata = entry.dir_entry.metadata()?;
+        let indent = "  ".repeat(entry.level);
+
+        let name = entry.name(self.opts);
+        let matched = if entry.highlight { " matched" } else { "" };
+        let name = self.format_name(&path, &name);
+        let columns = self.format_columns(&metadata);
+
+        if metadata.file_type().is_dir() {
+            // Children of a highlighted directory get a highlighted guide line
+            let in_match =
+                if entry.highlight || entry.first_matched_ancestor < entry.indent_state.len() {
+                    " in-match"
+                } else {
+                    ""
+                };
+            writeln!(
+                self.writer,
+                "{indent}<details class=\"dir{matched}\" open><summary><span class=\"name\">{name}</span>{columns}</summary>\n{indent}<div class=\"contents{in_match}\">"
+            )
+        } else {
+            writeln!(
+                self.writer,
+                "{indent}<div class=\"entry{matched}\"><span class=\"name\">{name}</span>{columns}</div>"
+            )
+        }
+    }
+
+    fn leave_dir(&mut self, entry: &Entry) -> Result<()> {
+        let indent = "  ".repeat(entry.level);
+        writeln!(self.writer, "{indent}</div>\n{indent}</details>")
+    }
+
+    fn end(&mut self, stats: (u64, u64)) -> Result<()> {
+        writeln!(
+            self.writer,
+            "</div>\n</details>\n<p class=\"report\">{}</p>\n</body>\n</html>",
+            format_report(stats)
+        )
+    }
+}
@@ -0,0 +1,126 @@
+use std::{
+    fs::{Metadata, read_link},
+    io::{Result, Write},
+    os::unix::fs::PermissionsExt,
+    path::Path,
+};
+
+use crate::cmd::{
+    display::{escape_json, format_date, format_permissions},
+    render::{Entry, Render, root_name},
+    root::Opts,
+};
+
+/// Renders the tree with the same schema as GNU tree's `-J` output.
+pub struct JsonRender<'a> {
+    opts: &'a Opts,
+    writer: &'a mut dyn Write,
+}
+
+impl<'a> JsonRender<'a> {
+    pub fn new(opts: &'a Opts, writer: &'a mut dyn Write) -> Self {
+        Self { opts, writer }
+    }
+
+    fn format_metadata(&self, metadata: &Metadata) -> String {
+        let mut fields = String::new();
+
+        if self.opts.print_permissions {
+            let mode = metadata.permissions().mode();
+            let perms_str = format_permissions(mode, metadata.file_type().is_dir());
+            fields.push_str(&format!(
+                ",\"mode\":\"{:04o}\",\"prot\":\"{}\"",
+                mode & 0o7777,
+                perms_str.trim_matches(['[', ']'])
+            ));
+        }
+
+        if self.opts.print_size && !metadata.file_type().is_dir() {
+            fields.push_str(&format!(",\"size\":{}", metadata.len()));
+        }
+
+        if self.opts.last_modify
+            && let Ok(mod_time) = metadata.modified()
+        {
+            fields.push_str(&format!(",\"time\":\"{}\"", format_date(mod_time)));
+        }
+
+        fields
+    }
+}
+
+impl Render for JsonRender<'_> {
+    fn begin(&mut self, path: &Path, metadata: &Metadata) -> Result<()> {
+        let name = root_name(path, self.opts)?;
+        writeln!(
+            self.writer,
+            "[\n  {{\"type\":\"directory\",\"name\":\"{}\"{},\"contents\":[",
+            escape_json(&name),
+            self.format_metadata(metadata)
+        )
+    }
+
+    fn entry(&mut self, entry: &Entry) -> Result<()> {
+        let path = entry.dir_entry.path();
+        let metadata = entry.dir_entry.metadata()?;
+        let file_type = metadata.file_type();
+        let indent = "  ".repeat(entry.level + 1);
+
+        let name = entry.name(self.opts);
+
+        let (kind, target) = if file_type.is_dir() {
+            ("directory", String::new())
+        } else if file_type.is_symlink() {
+            let target = read_link(&path)
+                .map(|target| format!(",\"target\":\"{}\"", escape_json(&target.to_string_lossy())))
+                .unwrap_or_default();
+            ("link", target)
+        } else {
+            ("file", String::new())
+        };
+
+        write!(
+            self.writer,
+            "{indent}{{\"type\":\"{kind}\",\"name\":\"{}\"{target}{}",
+            escape_json(&name),
+            self.format_metadata(&metadata)
+        )?;
+
+        if let Some(count) = entry.filelimit {
+            write!(self.writer, ",\"entries\":{count}")?;
+        }
+
+        if self.opts.grep_annotate
+            && let Some(grep_match) = entry.grep_match
+        {
+            write!(
+                self.writer,
+                ",\"matches\":{},\"line\":{},\"match\":\"{}\"",
+                grep_match.count,
+                grep_match.first_line_number,
+                escape_json(&grep_match.first_line)
+            )?;
+        }
+
+        if file_type.is_dir() {
+            writeln!(self.writer, ",\"contents\":[")
+        } else {
+            let separator = if entry.is_last { "" } else { "," };
+            writeln!(self.writer, "}}{separator}")
+        }
+    }
+
+    fn leave_dir(&mut self, entry: &Entry) -> Result<()> {
+        let indent = "  ".repeat(entry.level + 1);
+        let separator = if entry.is_last { "" } else { "," };
+        writeln!(self.writer, "{indent}]}}{separator}")
+    }
+
+    fn end(&mut self, stats: (u64, u64)) -> Result<()> {
+        writeln!(
+            self.writer,
+            "  ]}}\n,\n  {{\"type\":\"report\",\"directories\":{},\"files\":{}}}\n]",
+            stats.0, stats.1
+        )
+    }
+}
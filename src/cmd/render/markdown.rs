@@ -0,0 +1,118 @@
+use std::{
+    fs::Metadata,
+    io::{Result, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::cmd::{
+    render::{Entry, Render, entry_href, root_name},
+    root::Opts,
+};
+
+fn escape_markdown(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    // A leading list marker (followed by a space) would start a nested list inside the bullet
+    let digits = s.chars().take_while(char::is_ascii_digit).count();
+    let is_marker = match s[digits..].chars().next() {
+        Some('-' | '+') => digits == 0,
+        Some('.' | ')') => digits > 0,
+        _ => false,
+    };
+    let list_marker = (is_marker
+        && s[digits + 1..]
+            .chars()
+            .next()
+            .is_none_or(char::is_whitespace))
+    .then_some(digits);
+    for (i, c) in s.chars().enumerate() {
+        if list_marker == Some(i) {
+            escaped.push('\\');
+        }
+        // A line break would end the bullet, so it is written as a visible escape
+        match c {
+            '\n' => {
+                escaped.push_str("\\n");
+                continue;
+            }
+            '\r' => {
+                escaped.push_str("\\r");
+                continue;
+            }
+            _ => {}
+        }
+        if matches!(
+            c,
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|' | '!'
+        ) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Renders the tree as a nested Markdown bullet list.
+pub struct MarkdownRender<'a> {
+    opts: &'a Opts,
+    writer: &'a mut dyn Write,
+    root: PathBuf,
+}
+
+impl<'a> MarkdownRender<'a> {
+    pub fn new(opts: &'a Opts, writer: &'a mut dyn Write) -> Self {
+        Self {
+            opts,
+            writer,
+            root: PathBuf::new(),
+        }
+    }
+
+    fn write_item(
+        &mut self,
+        path: &Path,
+        name: &str,
+        is_dir: bool,
+        depth: usize,
+        bold: bool,
+    ) -> Result<()> {
+        let indent = "  ".repeat(depth);
+        let mut item = escape_markdown(name);
+        if is_dir {
+            item.push('/');
+        }
+
+        if let Some(base_href) = &self.opts.base_href {
+            item = format!("[{item}]({})", entry_href(base_href, &self.root, path));
+        }
+
+        if bold {
+            item = format!("**{item}**");
+        }
+
+        writeln!(self.writer, "{indent}- {item}")
+    }
+}
+
+impl Render for MarkdownRender<'_> {
+    fn begin(&mut self, path: &Path, _metadata: &Metadata) -> Result<()> {
+        self.root = path.to_path_buf();
+        let name = root_name(path, self.opts)?;
+        self.write_item(path, &name, true, 0, false)
+    }
+
+    fn entry(&mut self, entry: &Entry) -> Result<()> {
+        let is_dir = entry.dir_entry.file_type()?.is_dir();
+        let name = entry.name(self.opts);
+        self.write_item(
+            &entry.dir_entry.path(),
+            &name,
+            is_dir,
+            entry.level,
+            entry.highlight,
+        )
+    }
+
+    fn end(&mut self, _stats: (u64, u64)) -> Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,97 @@
+use std::{
+    fs::Metadata,
+    io::{Result, Write},
+    path::Path,
+};
+
+use crate::cmd::{
+    render::{Entry, Render, root_name},
+    root::Opts,
+};
+
+fn escape_mermaid(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("#quot;"),
+            '#' => escaped.push_str("#35;"),
+            '<' => escaped.push_str("#lt;"),
+            '>' => escaped.push_str("#gt;"),
+            // A line break would end the node definition, so it is written as a visible escape
+            '\n' => escaped.push_str("#92;n"),
+            '\r' => escaped.push_str("#92;r"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Renders the tree as a fenced Mermaid `graph TD` block.
+pub struct MermaidRender<'a> {
+    opts: &'a Opts,
+    writer: &'a mut dyn Write,
+    next_id: usize,
+    parents: Vec<usize>,
+    highlighted: Vec<usize>,
+}
+
+impl<'a> MermaidRender<'a> {
+    pub fn new(opts: &'a Opts, writer: &'a mut dyn Write) -> Self {
+        Self {
+            opts,
+            writer,
+            next_id: 0,
+            parents: Vec::new(),
+            highlighted: Vec::new(),
+        }
+    }
+
+    fn write_node(&mut self, name: &str, is_dir: bool) -> Result<usize> {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let (open, close) = if is_dir { ("[", "]") } else { ("(", ")") };
+        let node = format!("n{id}{open}\"{}\"{close}", escape_mermaid(name));
+        match self.parents.last() {
+            Some(parent) => writeln!(self.writer, "    n{parent} --> {node}")?,
+            None => writeln!(self.writer, "    {node}")?,
+        }
+        Ok(id)
+    }
+}
+
+impl Render for MermaidRender<'_> {
+    fn begin(&mut self, path: &Path, _metadata: &Metadata) -> Result<()> {
+        writeln!(self.writer, "```mermaid\ngraph TD")?;
+        let name = root_name(path, self.opts)?;
+        let id = self.write_node(&name, true)?;
+        self.parents.push(id);
+        Ok(())
+    }
+
+    fn entry(&mut self, entry: &Entry) -> Result<()> {
+        let is_dir = entry.dir_entry.file_type()?.is_dir();
+        let id = self.write_node(&entry.name(self.opts), is_dir)?;
+        if entry.highlight {
+            self.highlighted.push(id);
+        }
+        if is_dir {
+            self.parents.push(id);
+        }
+        Ok(())
+    }
+
+    fn leave_dir(&mut self, _entry: &Entry) -> Result<()> {
+        self.parents.pop();
+        Ok(())
+    }
+
+    fn end(&mut self, _stats: (u64, u64)) -> Result<()> {
+        if !self.highlighted.is_empty() {
+            let ids: Vec<String> = self.highlighted.iter().map(|id| format!("n{id}")).collect();
+            writeln!(self.writer, "    classDef matched font-weight:bold")?;
+            writeln!(self.writer, "    class {} matched", ids.join(","))?;
+        }
+        writeln!(self.writer, "```")
+    }
+}
@@ -0,0 +1,86 @@
+use std::{
+    fs::Metadata,
+    io::{Result, Write},
+    os::unix::fs::PermissionsExt,
+    path::Path,
+    time::UNIX_EPOCH,
+};
+
+use crate::cmd::{
+    display::{escape_json, format_permissions},
+    render::{Entry, Render, type_name},
+};
+
+/// Streams one JSON object per line for every visited entry, followed by a report record.
+pub struct NdjsonRender<'a> {
+    writer: &'a mut dyn Write,
+}
+
+impl<'a> NdjsonRender<'a> {
+    pub fn new(writer: &'a mut dyn Write) -> Self {
+        Self { writer }
+    }
+
+    fn write_record(
+        &mut self,
+        path: &Path,
+        metadata: &Metadata,
+        depth: usize,
+        matched: bool,
+        is_last: bool,
+        filelimit: Option<usize>,
+    ) -> Result<()> {
+        let file_type = metadata.file_type();
+        let kind = type_name(file_type);
+        let mode = metadata.permissions().mode();
+        let perms_str = format_permissions(mode, file_type.is_dir());
+        let mtime = metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map(|duration| duration.as_secs().to_string())
+            .unwrap_or_else(|| String::from("null"));
+        // Only directories not opened because of --filelimit have an entry count
+        let entries = filelimit
+            .map(|count| format!(",\"entries\":{count}"))
+            .unwrap_or_default();
+
+        writeln!(
+            self.writer,
+            "{{\"path\":\"{}\",\"depth\":{depth},\"type\":\"{kind}\",\"size\":{},\"mtime\":{mtime},\"mode\":\"{:04o}\",\"permissions\":\"{}\",\"matched\":{matched},\"is_last\":{is_last}{entries}}}",
+            escape_json(&path.to_string_lossy()),
+            metadata.len(),
+            mode & 0o7777,
+            perms_str.trim_matches(['[', ']'])
+        )?;
+        // Records are flushed as they come, so pipelines can process a slow walk incrementally
+        self.writer.flush()
+    }
+}
+
+impl Render for NdjsonRender<'_> {
+    fn begin(&mut self, path: &Path, metadata: &Metadata) -> Result<()> {
+        self.write_record(path, metadata, 0, false, true, None)
+    }
+
+    fn entry(&mut self, entry: &Entry) -> Result<()> {
+        let metadata = entry.dir_entry.metadata()?;
+        self.write_record(
+            &entry.dir_entry.path(),
+            &metadata,
+            entry.depth,
+            entry.matched,
+            entry.is_last,
+            entry.filelimit,
+        )
+    }
+
+    fn end(&mut self, stats: (u64, u64)) -> Result<()> {
+        writeln!(
+            self.writer,
+            "{{\"type\":\"report\",\"directories\":{},\"files\":{}}}",
+            stats.0, stats.1
+        )?;
+        self.writer.flush()
+    }
+}
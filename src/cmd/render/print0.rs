@@ -0,0 +1,36 @@
+use std::{
+    fs::Metadata,
+    io::{Result, Write},
+    os::unix::ffi::OsStrExt,
+    path::Path,
+};
+
+use crate::cmd::render::{Entry, Render};
+
+/// Prints the path of every displayed entry, NUL-separated, for `xargs -0`.
+pub struct Print0Render<'a> {
+    writer: &'a mut dyn Write,
+}
+
+impl<'a> Print0Render<'a> {
+    pub fn new(writer: &'a mut dyn Write) -> Self {
+        Self { writer }
+    }
+}
+
+impl Render for Print0Render<'_> {
+    fn begin(&mut self, _path: &Path, _metadata: &Metadata) -> Result<()> {
+        Ok(())
+    }
+
+    fn entry(&mut self, entry: &Entry) -> Result<()> {
+        // Write the raw bytes so that non UTF-8 names survive
+        self.writer
+            .write_all(entry.dir_entry.path().as_os_str().as_bytes())?;
+        self.writer.write_all(b"\0")
+    }
+
+    fn end(&mut self, _stats: (u64, u64)) -> Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,110 @@
+use std::{
+    fs::Metadata,
+    io::{Result, Write},
+    path::Path,
+};
+
+use crate::cmd::{
+    display::{Paint, escape_xml, format_entry_segments, format_report, format_root_line},
+    render::{Entry, Render},
+    root::Opts,
+};
+
+const FONT_SIZE: usize = 14;
+const CHAR_WIDTH: f64 = 8.4;
+const LINE_HEIGHT: usize = 18;
+const PADDING: usize = 12;
+const BACKGROUND: &str = "#1e1e1e";
+const FOREGROUND: &str = "#d4d4d4";
+const RED: &str = "#f14c4c";
+
+/// Renders the tree as an SVG image that looks like the terminal output.
+///
+/// Lines are buffered until the end since the size of the image depends on all of them.
+pub struct SvgRender<'a> {
+    opts: &'a Opts,
+    writer: &'a mut dyn Write,
+    lines: Vec<Vec<(String, Paint)>>,
+}
+
+impl<'a> SvgRender<'a> {
+    pub fn new(opts: &'a Opts, writer: &'a mut dyn Write) -> Self {
+        Self {
+            opts,
+            writer,
+            lines: Vec::new(),
+        }
+    }
+}
+
+impl Render for SvgRender<'_> {
+    fn begin(&mut self, path: &Path, metadata: &Metadata) -> Result<()> {
+        let root_line = format_root_line(path, metadata, self.opts)?;
+        self.lines.push(vec![(root_line, Paint::Plain)]);
+        Ok(())
+    }
+
+    fn entry(&mut self, entry: &Entry) -> Result<()> {
+        let segments = format_entry_segments(
+            entry.dir_entry,
+            &entry.name(self.opts),
+            self.opts,
+            entry.indent_state,
+            entry.is_last,
+            entry.highlight,
+            entry.first_matched_ancestor,
+        )?;
+        self.lines.push(segments);
+        Ok(())
+    }
+
+    fn end(&mut self, stats: (u64, u64)) -> Result<()> {
+        self.lines.push(Vec::new());
+        self.lines.push(vec![(format_report(stats), Paint::Plain)]);
+
+        let columns = self
+            .lines
+            .iter()
+            .map(|line| line.iter().map(|(text, _)| text.chars().count()).sum())
+            .max()
+            .unwrap_or(0);
+        let width = (columns as f64 * CHAR_WIDTH).ceil() as usize + 2 * PADDING;
+        let height = self.lines.len() * LINE_HEIGHT + 2 * PADDING;
+
+        writeln!(
+            self.writer,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" viewBox=\"0 0 {width} {height}\">"
+        )?;
+        writeln!(
+            self.writer,
+            "<rect width=\"100%\" height=\"100%\" fill=\"{BACKGROUND}\"/>"
+        )?;
+        writeln!(
+            self.writer,
+            "<g font-family=\"monospace\" font-size=\"{FONT_SIZE}\" fill=\"{FOREGROUND}\" xml:space=\"preserve\">"
+        )?;
+
+        for (idx, line) in self.lines.iter().enumerate() {
+            if line.is_empty() {
+                continue;
+            }
+            let y = PADDING + (idx + 1) * LINE_HEIGHT - (LINE_HEIGHT - FONT_SIZE);
+            let mut text = String::new();
+            for (segment, paint) in line {
+                let segment = escape_xml(segment);
+                match paint {
+                    Paint::Plain => text.push_str(&segment),
+                    Paint::Red => {
+                        text.push_str(&format!("<tspan fill=\"{RED}\">{segment}</tspan>"))
+                    }
+                    Paint::RedBold => text.push_str(&format!(
+                        "<tspan fill=\"{RED}\" font-weight=\"bold\">{segment}</tspan>"
+                    )),
+                }
+            }
+            writeln!(self.writer, "<text x=\"{PADDING}\" y=\"{y}\">{text}</text>")?;
+        }
+
+        writeln!(self.writer, "</g>\n</svg>")
+    }
+}
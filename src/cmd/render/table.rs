@@ -0,0 +1,92 @@
+use std::{
+    fs::Metadata,
+    io::{Result, Write},
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+};
+
+use crate::cmd::{
+    display::format_iso_date,
+    render::{Entry, Render, type_name},
+};
+
+const HEADER: [&str; 7] = ["path", "depth", "type", "size", "mtime", "mode", "matched"];
+
+fn quote_csv(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn escape_tsv(field: &str) -> String {
+    field
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+/// Renders a flat table with one row per entry, as CSV or TSV.
+pub struct TableRender<'a> {
+    writer: &'a mut dyn Write,
+    separator: char,
+    root: PathBuf,
+}
+
+impl<'a> TableRender<'a> {
+    pub fn csv(writer: &'a mut dyn Write) -> Self {
+        Self {
+            writer,
+            separator: ',',
+            root: PathBuf::new(),
+        }
+    }
+
+    pub fn tsv(writer: &'a mut dyn Write) -> Self {
+        Self {
+            writer,
+            separator: '\t',
+            root: PathBuf::new(),
+        }
+    }
+
+    fn write_row(&mut self, fields: &[&str]) -> Result<()> {
+        let escape = if self.separator == ',' {
+            quote_csv
+        } else {
+            escape_tsv
+        };
+        let row: Vec<String> = fields.iter().map(|field| escape(field)).collect();
+        writeln!(self.writer, "{}", row.join(&self.separator.to_string()))
+    }
+}
+
+impl Render for TableRender<'_> {
+    fn begin(&mut self, path: &Path, _metadata: &Metadata) -> Result<()> {
+        self.root = path.to_path_buf();
+        self.write_row(&HEADER)
+    }
+
+    fn entry(&mut self, entry: &Entry) -> Result<()> {
+        let path = entry.dir_entry.path();
+        let metadata = entry.dir_entry.metadata()?;
+        let relative = path.strip_prefix(&self.root).unwrap_or(&path);
+        let mtime = metadata.modified().map(format_iso_date).unwrap_or_default();
+
+        self.write_row(&[
+            &relative.to_string_lossy(),
+            &entry.depth.to_string(),
+            type_name(metadata.file_type()),
+            &metadata.len().to_string(),
+            &mtime,
+            &format!("{:04o}", metadata.permissions().mode() & 0o7777),
+            if entry.matched { "true" } else { "false" },
+        ])
+    }
+
+    fn end(&mut self, _stats: (u64, u64)) -> Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,56 @@
+use std::{
+    fs::Metadata,
+    io::{Result, Write},
+    path::Path,
+};
+
+use crate::cmd::{
+    display::{
+        format_entry_line, format_filelimit, format_grep_match, format_report, format_root_line,
+    },
+    render::{Entry, Render},
+    root::Opts,
+};
+
+pub struct TextRender<'a> {
+    opts: &'a Opts,
+    writer: &'a mut dyn Write,
+}
+
+impl<'a> TextRender<'a> {
+    pub fn new(opts: &'a Opts, writer: &'a mut dyn Write) -> Self {
+        Self { opts, writer }
+    }
+}
+
+impl Render for TextRender<'_> {
+    fn begin(&mut self, path: &Path, metadata: &Metadata) -> Result<()> {
+        let root_line = format_root_line(path, metadata, self.opts)?;
+        writeln!(self.writer, "{root_line}")
+    }
+
+    fn entry(&mut self, entry: &Entry) -> Result<()> {
+        let mut line = format_entry_line(
+            entry.dir_entry,
+            &entry.name(self.opts),
+            self.opts,
+            entry.indent_state,
+            entry.is_last,
+            entry.highlight,
+            entry.first_matched_ancestor,
+        )?;
+        if let Some(count) = entry.filelimit {
+            line.push_str(&format!(" [{}]", format_filelimit(count)));
+        }
+        if self.opts.grep_annotate
+            && let Some(grep_match) = entry.grep_match
+        {
+            line.push_str(&format!(" [{}]", format_grep_match(grep_match)));
+        }
+        writeln!(self.writer, "{line}")
+    }
+
+    fn end(&mut self, stats: (u64, u64)) -> Result<()> {
+        writeln!(self.writer, "\n{}", format_report(stats))
+    }
+}
@@ -0,0 +1,107 @@
+use std::{
+    fs::{Metadata, read_link},
+    io::{Result, Write},
+    os::unix::fs::PermissionsExt,
+    path::Path,
+};
+
+use crate::cmd::{
+    display::{escape_xml, format_date, format_permissions},
+    render::{Entry, Render, root_name},
+    root::Opts,
+};
+
+/// Renders the tree as an XML document, in the layout of GNU tree's `-X` output.
+pub struct XmlRender<'a> {
+    opts: &'a Opts,
+    writer: &'a mut dyn Write,
+}
+
+impl<'a> XmlRender<'a> {
+    pub fn new(opts: &'a Opts, writer: &'a mut dyn Write) -> Self {
+        Self { opts, writer }
+    }
+
+    fn format_attributes(&self, metadata: &Metadata) -> String {
+        let mut attributes = String::new();
+
+        if self.opts.print_permissions {
+            let mode = metadata.permissions().mode();
+            let perms_str = format_permissions(mode, metadata.file_type().is_dir());
+            attributes.push_str(&format!(
+                " mode=\"{:04o}\" prot=\"{}\"",
+                mode & 0o7777,
+                perms_str.trim_matches(['[', ']'])
+            ));
+        }
+
+        if self.opts.print_size && !metadata.file_type().is_dir() {
+            attributes.push_str(&format!(" size=\"{}\"", metadata.len()));
+        }
+
+        if self.opts.last_modify
+            && let Ok(mod_time) = metadata.modified()
+        {
+            attributes.push_str(&format!(" time=\"{}\"", format_date(mod_time)));
+        }
+
+        attributes
+    }
+}
+
+impl Render for XmlRender<'_> {
+    fn begin(&mut self, path: &Path, metadata: &Metadata) -> Result<()> {
+        let name = root_name(path, self.opts)?;
+        writeln!(
+            self.writer,
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<tree>\n  <directory name=\"{}\"{}>",
+            escape_xml(&name),
+            self.format_attributes(metadata)
+        )
+    }
+
+    fn entry(&mut self, entry: &Entry) -> Result<()> {
+        let path = entry.dir_entry.path();
+        let metadata = entry.dir_entry.metadata()?;
+        let file_type = metadata.file_type();
+        let indent = "  ".repeat(entry.level + 1);
+
+        let name = entry.name(self.opts);
+
+        let (tag, target) = if file_type.is_dir() {
+            ("directory", String::new())
+        } else if file_type.is_symlink() {
+            let target = read_link(&path)
+                .map(|target| format!(" target=\"{}\"", escape_xml(&target.to_string_lossy())))
+                .unwrap_or_default();
+            ("link", target)
+        } else {
+            ("file", String::new())
+        };
+
+        let mut attributes = self.format_attributes(&metadata);
+        if let Some(count) = entry.filelimit {
+            attributes.push_str(&format!(" entries=\"{count}\""));
+        }
+
+        let close = if file_type.is_dir() { "" } else { "/" };
+        writeln!(
+            self.writer,
+            "{indent}<{tag} name=\"{}\"{target}{attributes}{close}>",
+            escape_xml(&name),
+        )
+    }
+
+    fn leave_dir(&mut self, entry: &Entry) -> Result<()> {
+        let indent = "  ".repeat(entry.level + 1);
+        writeln!(self.writer, "{indent}</directory>")
+    }
+
+    fn end(&mut self, stats: (u64, u64)) -> Result<()> {
+        writeln!(
+            self.writer,
+            "  </directory>\n  <report>\n    <directories>{}</directories>\n    <files>{}</files>\n  </report>\n</tree>",
+            stats.0, stats.1
+        )
+    }
+}
@@ -0,0 +1,114 @@
+use std::{
+    fs::Metadata,
+    io::{Result, Write},
+    os::unix::fs::PermissionsExt,
+    path::Path,
+};
+
+use crate::cmd::{
+    display::{escape_json, format_iso_date},
+    render::{Entry, Render, root_name, type_name},
+    root::Opts,
+};
+
+/// Renders the tree as nested YAML mappings, keyed by entry name.
+///
+/// Keys are always double-quoted (YAML double-quoted scalars share JSON's escaping),
+/// so that any file name round-trips.
+pub struct YamlRender<'a> {
+    opts: &'a Opts,
+    writer: &'a mut dyn Write,
+    // Whether each open directory already has a child, an empty one is closed with `{}`
+    open_dirs: Vec<bool>,
+}
+
+impl<'a> YamlRender<'a> {
+    pub fn new(opts: &'a Opts, writer: &'a mut dyn Write) -> Self {
+        Self {
+            opts,
+            writer,
+            open_dirs: Vec::new(),
+        }
+    }
+
+    fn write_node(
+        &mut self,
+        name: &str,
+        metadata: &Metadata,
+        depth: usize,
+        filelimit: Option<usize>,
+    ) -> Result<()> {
+        if let Some(has_children) = self.open_dirs.last_mut()
+            && !*has_children
+        {
+            *has_children = true;
+            writeln!(self.writer)?;
+        }
+
+        let indent = " ".repeat(4 * depth);
+        let file_type = metadata.file_type();
+        writeln!(self.writer, "{indent}\"{}\":", escape_json(name))?;
+        writeln!(self.writer, "{indent}  type: {}", type_name(file_type))?;
+
+        if self.opts.print_size && !file_type.is_dir() {
+            writeln!(self.writer, "{indent}  size: {}", metadata.len())?;
+        }
+
+        if self.opts.last_modify
+            && let Ok(mod_time) = metadata.modified()
+        {
+            writeln!(
+                self.writer,
+                "{indent}  mtime: \"{}\"",
+                format_iso_date(mod_time)
+            )?;
+        }
+
+        if self.opts.print_permissions {
+            let mode = metadata.permissions().mode();
+            writeln!(self.writer, "{indent}  mode: \"{:04o}\"", mode & 0o7777)?;
+        }
+
+        if let Some(count) = filelimit {
+            writeln!(self.writer, "{indent}  entries: {count}")?;
+        }
+
+        if file_type.is_dir() {
+            write!(self.writer, "{indent}  contents:")?;
+            self.open_dirs.push(false);
+        }
+        Ok(())
+    }
+
+    fn close_dir(&mut self) -> Result<()> {
+        match self.open_dirs.pop() {
+            Some(false) => writeln!(self.writer, " {{}}"),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Render for YamlRender<'_> {
+    fn begin(&mut self, path: &Path, metadata: &Metadata) -> Result<()> {
+        let name = root_name(path, self.opts)?;
+        self.write_node(&name, metadata, 0, None)
+    }
+
+    fn entry(&mut self, entry: &Entry) -> Result<()> {
+        let metadata = entry.dir_entry.metadata()?;
+        self.write_node(
+            &entry.name(self.opts),
+            &metadata,
+            entry.level,
+            entry.filelimit,
+        )
+    }
+
+    fn leave_dir(&mut self, _entry: &Entry) -> Result<()> {
+        self.close_dir()
+    }
+
+    fn end(&mut self, _stats: (u64, u64)) -> Result<()> {
+        self.close_dir()
+    }
+}
@@ -1,26 +1,106 @@
-use std::{io, path::Path};
+use std::{
+    fs::{metadata, read_to_string},
+    io,
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use glob::Pattern;
+use regex::{Regex, RegexBuilder};
 
-use crate::cmd::traversal::print_tree;
+use crate::cmd::{
+    expr::Expr,
+    traversal::print_tree,
+    users::{lookup_gid, lookup_uid},
+};
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+    Xml,
+    Html,
+    Ndjson,
+    Dot,
+    Markdown,
+    Mermaid,
+    Csv,
+    Tsv,
+    Print0,
+    Svg,
+    Yaml,
+}
+
+/// Entry types accepted by --type, with the letters used by find.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum EntryType {
+    /// Regular file
+    #[value(name = "f")]
+    File,
+    /// Directory
+    #[value(name = "d")]
+    Directory,
+    /// Symbolic link
+    #[value(name = "l")]
+    Symlink,
+    /// Executable regular file
+    #[value(name = "x")]
+    Executable,
+    /// Named pipe
+    #[value(name = "p")]
+    Fifo,
+    /// Socket
+    #[value(name = "s")]
+    Socket,
+    /// Block device
+    #[value(name = "b")]
+    BlockDevice,
+    /// Character device
+    #[value(name = "c")]
+    CharDevice,
+}
 
 #[derive(Default, Debug)]
 pub struct Opts {
     pub show_hidden: bool,
     pub ascii: bool,
+    pub base_href: Option<String>,
     pub exclude_patterns: Vec<Pattern>,
+    pub exclude_regex_patterns: Vec<Regex>,
     pub dir_only: bool,
+    pub entry_types: Vec<EntryType>,
+    pub filelimit: Option<usize>,
     pub full_path: bool,
+    pub gid: Option<u32>,
+    pub gitignore: bool,
+    pub grep: Option<Regex>,
+    pub grep_annotate: bool,
+    pub grep_binary: bool,
     pub highlight: bool,
     pub icons: bool,
+    pub ignore_case: bool,
     pub last_modify: bool,
     pub level: Option<u32>,
+    pub match_dirs: bool,
+    pub match_files: bool,
+    pub match_path: bool,
+    pub max_size: Option<u64>,
+    pub min_depth: Option<u32>,
+    pub min_size: Option<u64>,
+    pub newer: Option<SystemTime>,
     pub no_indent: bool,
+    pub older: Option<SystemTime>,
+    pub output_format: OutputFormat,
     pub print_size: bool,
     pub print_permissions: bool,
     pub patterns: Vec<Pattern>,
+    pub prune: bool,
+    pub regex_patterns: Vec<Regex>,
     pub sort_by_time: bool,
+    pub uid: Option<u32>,
+    pub where_expr: Option<Expr>,
 }
 
 #[derive(Parser, Debug)]
@@ -28,21 +108,101 @@ pub struct Cmd {
     #[arg(default_value = ".", help = "Path to the directory.")]
     pub path: String,
 
+    #[arg(
+        short = '0',
+        long = "print0",
+        group = "output",
+        help = "Print only the displayed paths, separated by NUL characters (for xargs -0)."
+    )]
+    pub print0: bool,
+
     #[arg(short = 'a', long = "all", help = "All (include hidden) directories.")]
     pub show_hidden: bool,
 
     #[arg(short = 'A', long = "ascii", help = "Use ascii characters to indent.")]
     pub ascii: bool,
 
+    #[arg(
+        long = "base-href",
+        help = "Base URL used to turn entries into links in HTML and Markdown output."
+    )]
+    pub base_href: Option<String>,
+
+    #[arg(
+        long = "changed-within",
+        value_name = "DURATION",
+        value_parser = parse_time_ago,
+        help = "List only files modified within the duration, e.g. 30m, 2h, 3d or 1w."
+    )]
+    pub changed_within: Option<SystemTime>,
+
     #[arg(short = 'd', long = "directories", help = "List directories only.")]
     pub dir_only: bool,
 
     #[arg(short = 'D', long = "date", help = "Print last modification date.")]
     pub last_modify: bool,
 
+    #[arg(
+        long = "dot",
+        group = "output",
+        help = "Print the tree as a Graphviz DOT digraph."
+    )]
+    pub dot: bool,
+
+    #[arg(
+        long = "format",
+        group = "output",
+        value_enum,
+        help = "Output format (-J, -X, -H, --ndjson, --dot and -0 are shorthands)."
+    )]
+    pub format: Option<OutputFormat>,
+
+    #[arg(
+        long = "filelimit",
+        help = "Do not descend into directories with more than this many entries."
+    )]
+    pub filelimit: Option<usize>,
+
     #[arg(short = 'f', long = "full", help = "Print full path prefix.")]
     pub full_path: bool,
 
+    #[arg(
+        long = "gitignore",
+        help = "Ignore files/folders listed in .gitignore, .ignore and git exclude files."
+    )]
+    pub gitignore: bool,
+
+    #[arg(
+        long = "grep",
+        help = "List only files whose contents match the regular expression (binary files are skipped)."
+    )]
+    pub grep: Option<String>,
+
+    #[arg(
+        long = "grep-annotate",
+        help = "Show the match count and first matching line of files listed by --grep."
+    )]
+    pub grep_annotate: bool,
+
+    #[arg(long = "grep-binary", help = "Also search binary files with --grep.")]
+    pub grep_binary: bool,
+
+    #[arg(
+        long = "group",
+        value_name = "GROUP",
+        value_parser = parse_group,
+        help = "List only entries owned by this group name or gid (ancestor directories are kept)."
+    )]
+    pub gid: Option<u32>,
+
+    #[arg(
+        short = 'H',
+        long = "html",
+        group = "output",
+        help = "Print the tree as an HTML page with collapsible directories."
+    )]
+    pub html: bool,
+
     #[arg(
         long = "highlight",
         help = "Enable highlight on matching directory and their contents."
@@ -55,6 +215,20 @@ pub struct Cmd {
     )]
     pub icons: bool,
 
+    #[arg(
+        long = "ignore-case",
+        help = "Match -P, -I and --grep patterns case-insensitively."
+    )]
+    pub ignore_case: bool,
+
+    #[arg(
+        short = 'J',
+        long = "json",
+        group = "output",
+        help = "Print the tree as JSON (same schema as GNU tree -J)."
+    )]
+    pub json: bool,
+
     #[arg(
         short = 'L',
         long = "level",
@@ -62,6 +236,68 @@ pub struct Cmd {
     )]
     pub level: Option<u32>,
 
+    #[arg(
+        long = "ndjson",
+        group = "output",
+        help = "Stream one JSON object per entry (newline-delimited JSON)."
+    )]
+    pub ndjson: bool,
+
+    #[arg(
+        long = "match-dirs",
+        conflicts_with = "match_files",
+        help = "Match -P patterns against directories only (and list their contents)."
+    )]
+    pub match_dirs: bool,
+
+    #[arg(
+        long = "match-files",
+        help = "Match -P patterns against files only (and list the path to them)."
+    )]
+    pub match_files: bool,
+
+    #[arg(
+        long = "match-path",
+        help = "Match -P and -I patterns against the path relative to the root instead of the name."
+    )]
+    pub match_path: bool,
+
+    #[arg(
+        long = "max-size",
+        value_parser = parse_size,
+        help = "List only files up to this size, e.g. 1K, 1.5M or 2GB (K and KB are 1024 bytes as printed by -s, kB is 1000)."
+    )]
+    pub max_size: Option<u64>,
+
+    #[arg(
+        long = "min-depth",
+        help = "List only entries from this level on, the levels above are collapsed into their path."
+    )]
+    pub min_depth: Option<u32>,
+
+    #[arg(
+        long = "min-size",
+        value_parser = parse_size,
+        help = "List only files of at least this size, e.g. 10M (same units as --max-size)."
+    )]
+    pub min_size: Option<u64>,
+
+    #[arg(
+        long = "newer",
+        value_name = "TIME",
+        value_parser = parse_time,
+        help = "List only files modified after a date (YYYY-MM-DD[ HH:MM[:SS]], UTC) or a duration ago."
+    )]
+    pub newer: Option<SystemTime>,
+
+    #[arg(
+        long = "newer-than",
+        value_name = "PATH",
+        value_parser = parse_reference_time,
+        help = "List only files modified after the given file."
+    )]
+    pub newer_than: Option<SystemTime>,
+
     #[arg(short = 'i', long = "no-indent", help = "Disable indentation.")]
     pub no_indent: bool,
 
@@ -72,6 +308,21 @@ pub struct Cmd {
     )]
     pub exclude: Vec<String>,
 
+    #[arg(
+        long = "exclude-from",
+        value_name = "FILE",
+        help = "Read -I patterns from a file, one per line (blank lines and # comments are skipped)."
+    )]
+    pub exclude_from: Vec<String>,
+
+    #[arg(
+        long = "older",
+        value_name = "TIME",
+        value_parser = parse_time,
+        help = "List only files modified before a date or a duration ago (same format as --newer)."
+    )]
+    pub older: Option<SystemTime>,
+
     #[arg(short = 's', long = "size", help = "Print file size.")]
     pub print_size: bool,
 
@@ -81,52 +332,390 @@ pub struct Cmd {
     #[arg(
         short = 'P',
         long = "pattern",
-        help = "List only directories that match the wild-card pattern. May have multiple -P."
+        help = "List only entries whose name (path with --match-path) matches the wild-card pattern (directories only with --match-dirs, files only with --match-files). May have multiple -P."
     )]
     pub pattern: Vec<String>,
 
+    #[arg(
+        long = "pattern-from",
+        value_name = "FILE",
+        help = "Read -P patterns from a file, one per line (blank lines and # comments are skipped)."
+    )]
+    pub pattern_from: Vec<String>,
+
+    #[arg(
+        long = "prune",
+        help = "Do not list directories that end up with nothing to display."
+    )]
+    pub prune: bool,
+
+    #[arg(
+        long = "regex",
+        help = "Treat -P and -I patterns as regular expressions (or prefix a single pattern with re:)."
+    )]
+    pub regex: bool,
+
     #[arg(short = 't', long = "time", help = "Sort by last modification time.")]
     pub sort_by_time: bool,
+
+    #[arg(
+        long = "type",
+        value_name = "TYPES",
+        value_enum,
+        value_delimiter = ',',
+        help = "List only entries of these types, given as comma separated letters (f, d, l, x, p, s, b, c), e.g. x,l."
+    )]
+    pub entry_types: Vec<EntryType>,
+
+    #[arg(
+        long = "user",
+        value_name = "USER",
+        value_parser = parse_user,
+        help = "List only entries owned by this user name or uid (ancestor directories are kept)."
+    )]
+    pub uid: Option<u32>,
+
+    #[arg(
+        long = "where",
+        value_name = "EXPR",
+        help = "List only entries matching an expression, e.g. 'ext == rs and size > 10k and not path ~ \"tests/**\"'."
+    )]
+    pub where_expr: Option<String>,
+
+    #[arg(
+        short = 'X',
+        long = "xml",
+        group = "output",
+        help = "Print the tree as XML (same layout as GNU tree -X)."
+    )]
+    pub xml: bool,
 }
 
+const REGEX_PREFIX: &str = "re:";
+
 fn parse_glob_pattern(s: &str) -> Result<Pattern, String> {
     Pattern::new(s).map_err(|err| err.to_string())
 }
 
+fn parse_regex_pattern(s: &str, ignore_case: bool) -> Result<Regex, String> {
+    RegexBuilder::new(s)
+        .case_insensitive(ignore_case)
+        .build()
+        .map_err(|err| err.to_string())
+}
+
+/// Splits patterns into globs and regexes, patterns are regexes with --regex or a `re:` prefix.
+fn parse_patterns(
+    patterns: &[String],
+    regex: bool,
+    ignore_case: bool,
+) -> Result<(Vec<Pattern>, Vec<Regex>), String> {
+    let mut globs = Vec::new();
+    let mut regexes = Vec::new();
+    for pattern in patterns {
+        match pattern.strip_prefix(REGEX_PREFIX) {
+            Some(pattern) => regexes.push(parse_regex_pattern(pattern, ignore_case)?),
+            None if regex => regexes.push(parse_regex_pattern(pattern, ignore_case)?),
+            None => globs.push(parse_glob_pattern(pattern)?),
+        }
+    }
+    Ok((globs, regexes))
+}
+
+/// Reads pattern files with one pattern per line, errors point to the line of the invalid pattern.
+fn parse_pattern_files(
+    files: &[String],
+    regex: bool,
+    ignore_case: bool,
+) -> Result<(Vec<Pattern>, Vec<Regex>), String> {
+    let mut globs = Vec::new();
+    let mut regexes = Vec::new();
+    for file in files {
+        let content =
+            read_to_string(file).map_err(|e| format!("Could not read pattern file {file}: {e}"))?;
+        for (index, line) in content.lines().enumerate() {
+            // Trailing spaces are easy to miss and would make the pattern never match
+            let line = line.trim_end();
+            if line.is_empty() || line.trim_start().starts_with('#') {
+                continue;
+            }
+            let (line_globs, line_regexes) =
+                parse_patterns(&[line.to_string()], regex, ignore_case)
+                    .map_err(|e| format!("Invalid pattern: {file}:{}: {e}", index + 1))?;
+            globs.extend(line_globs);
+            regexes.extend(line_regexes);
+        }
+    }
+    Ok((globs, regexes))
+}
+
+/// Parses a size such as `512`, `10K`, `1.5MiB` or `2GB`: `K`, `KB` and `KiB` are powers of 1024,
+/// like the sizes printed by -s, while SI units with a lowercase prefix (`kB`, `mB`, ...) are powers of 1000.
+pub fn parse_size(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let split = s
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    let number: f64 = number.parse().map_err(|_| format!("invalid size `{s}`"))?;
+
+    let unit = unit.trim();
+    let multiplier: u64 = match unit {
+        "kB" => 1_000,
+        "mB" => 1_000_000,
+        "gB" => 1_000_000_000,
+        "tB" => 1_000_000_000_000,
+        _ => match unit.to_ascii_uppercase().as_str() {
+            "" | "B" => 1,
+            "K" | "KB" | "KIB" => 1 << 10,
+            "M" | "MB" | "MIB" => 1 << 20,
+            "G" | "GB" | "GIB" => 1 << 30,
+            "T" | "TB" | "TIB" => 1 << 40,
+            _ => return Err(format!("invalid size unit `{unit}`")),
+        },
+    };
+    let size = number * multiplier as f64;
+    // u64::MAX rounds up to 2^64 as a float, which is already out of range
+    if size >= u64::MAX as f64 {
+        return Err(format!("size `{s}` is too large"));
+    }
+    Ok(size as u64)
+}
+
+/// Parses a duration such as `45s`, `30m`, `2h`, `3d`, `1w` or `1h30m`.
+fn parse_duration(s: &str) -> Result<Duration, String> {
+    let mut secs = 0;
+    let mut rest = s.trim();
+    if rest.is_empty() {
+        return Err(String::from("empty duration"));
+    }
+    while !rest.is_empty() {
+        let split = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let (number, tail) = rest.split_at(split);
+        let number: u64 = number
+            .parse()
+            .map_err(|_| format!("invalid duration `{s}`"))?;
+        let unit_len = tail
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(tail.len());
+        let (unit, tail) = tail.split_at(unit_len);
+        let multiplier = match unit {
+            "s" => 1,
+            "m" => 60,
+            "h" => 60 * 60,
+            "d" => 24 * 60 * 60,
+            "w" => 7 * 24 * 60 * 60,
+            _ => {
+                return Err(format!(
+                    "invalid duration unit `{unit}` (expected s, m, h, d or w)"
+                ));
+            }
+        };
+        secs = number
+            .checked_mul(multiplier)
+            .and_then(|duration| duration.checked_add(secs))
+            .ok_or_else(|| format!("duration `{s}` is too long"))?;
+        rest = tail;
+    }
+    Ok(Duration::from_secs(secs))
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if (year % 4 == 0 && year % 100 != 0) || year % 400 == 0 => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days between 1970-01-01 and a date of the proleptic Gregorian calendar.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// Parses a UTC date as printed by -D: `YYYY-MM-DD`, optionally followed by `HH:MM[:SS]`.
+fn parse_date(s: &str) -> Option<SystemTime> {
+    let (date, time) = match s.trim().split_once([' ', 'T']) {
+        Some((date, time)) => (date, Some(time.trim_end_matches('Z'))),
+        None => (s.trim(), None),
+    };
+
+    let date: Vec<i64> = date
+        .split('-')
+        .map(|part| part.parse().ok())
+        .collect::<Option<_>>()?;
+    let [year, month, day] = date[..] else {
+        return None;
+    };
+    // Years are bounded so that the number of seconds can't overflow
+    if !(1970..=9999).contains(&year)
+        || !(1..=12).contains(&month)
+        || !(1..=days_in_month(year, month)).contains(&day)
+    {
+        return None;
+    }
+
+    let time: Vec<i64> = match time {
+        Some(time) => time
+            .split(':')
+            .map(|part| part.parse().ok())
+            .collect::<Option<_>>()?,
+        None => vec![0, 0],
+    };
+    let (hour, minute, second) = match time[..] {
+        [hour, minute] => (hour, minute, 0),
+        [hour, minute, second] => (hour, minute, second),
+        _ => return None,
+    };
+    if !(0..24).contains(&hour) || !(0..60).contains(&minute) || !(0..60).contains(&second) {
+        return None;
+    }
+
+    let secs = days_from_civil(year, month, day) * 86400 + hour * 3600 + minute * 60 + second;
+    Some(UNIX_EPOCH + Duration::from_secs(u64::try_from(secs).ok()?))
+}
+
+fn parse_time_ago(s: &str) -> Result<SystemTime, String> {
+    let duration = parse_duration(s)?;
+    Ok(SystemTime::now()
+        .checked_sub(duration)
+        .unwrap_or(UNIX_EPOCH))
+}
+
+/// Parses either a date or a duration before now.
+pub fn parse_time(s: &str) -> Result<SystemTime, String> {
+    match parse_date(s) {
+        Some(time) => Ok(time),
+        None => parse_time_ago(s).map_err(|_| {
+            format!("`{s}` is neither a date (YYYY-MM-DD[ HH:MM[:SS]]) nor a duration")
+        }),
+    }
+}
+
+/// Modification time of a reference file.
+fn parse_reference_time(s: &str) -> Result<SystemTime, String> {
+    metadata(s)
+        .and_then(|metadata| metadata.modified())
+        .map_err(|err| err.to_string())
+}
+
+fn parse_user(s: &str) -> Result<u32, String> {
+    s.parse()
+        .ok()
+        .or_else(|| lookup_uid(s))
+        .ok_or_else(|| format!("no such user `{s}`"))
+}
+
+fn parse_group(s: &str) -> Result<u32, String> {
+    s.parse()
+        .ok()
+        .or_else(|| lookup_gid(s))
+        .ok_or_else(|| format!("no such group `{s}`"))
+}
+
+fn output_format(cmd: &Cmd) -> OutputFormat {
+    if let Some(format) = cmd.format {
+        format
+    } else if cmd.json {
+        OutputFormat::Json
+    } else if cmd.xml {
+        OutputFormat::Xml
+    } else if cmd.html {
+        OutputFormat::Html
+    } else if cmd.ndjson {
+        OutputFormat::Ndjson
+    } else if cmd.dot {
+        OutputFormat::Dot
+    } else if cmd.print0 {
+        OutputFormat::Print0
+    } else {
+        OutputFormat::Text
+    }
+}
+
 fn cmd_to_opts(cmd: &Cmd) -> Result<Opts, String> {
-    let glob_patterns: Vec<Pattern> = cmd
-        .pattern
-        .iter()
-        .map(|p| parse_glob_pattern(p))
-        .collect::<Result<Vec<_>, _>>()?;
-    let exclude_patterns: Vec<Pattern> = cmd
-        .exclude
-        .iter()
-        .map(|p| parse_glob_pattern(p))
-        .collect::<Result<Vec<_>, _>>()?;
+    let invalid_pattern = |e: String| format!("Invalid pattern: {e}");
+    let (mut glob_patterns, mut regex_patterns) =
+        parse_patterns(&cmd.pattern, cmd.regex, cmd.ignore_case).map_err(invalid_pattern)?;
+    let (mut exclude_patterns, mut exclude_regex_patterns) =
+        parse_patterns(&cmd.exclude, cmd.regex, cmd.ignore_case).map_err(invalid_pattern)?;
+
+    let (file_globs, file_regexes) =
+        parse_pattern_files(&cmd.pattern_from, cmd.regex, cmd.ignore_case)?;
+    glob_patterns.extend(file_globs);
+    regex_patterns.extend(file_regexes);
+    let (file_globs, file_regexes) =
+        parse_pattern_files(&cmd.exclude_from, cmd.regex, cmd.ignore_case)?;
+    exclude_patterns.extend(file_globs);
+    exclude_regex_patterns.extend(file_regexes);
+    let grep = cmd
+        .grep
+        .as_deref()
+        .map(|grep| parse_regex_pattern(grep, cmd.ignore_case))
+        .transpose()
+        .map_err(invalid_pattern)?;
+    let where_expr = cmd
+        .where_expr
+        .as_deref()
+        .map(Expr::parse)
+        .transpose()
+        .map_err(|e| format!("Invalid expression: {e}"))?;
     Ok(Opts {
         show_hidden: cmd.show_hidden,
         ascii: cmd.ascii,
+        base_href: cmd.base_href.clone(),
         exclude_patterns,
+        exclude_regex_patterns,
         dir_only: cmd.dir_only,
+        entry_types: cmd.entry_types.clone(),
+        filelimit: cmd.filelimit,
         full_path: cmd.full_path,
+        gid: cmd.gid,
+        gitignore: cmd.gitignore,
+        grep,
+        grep_annotate: cmd.grep_annotate,
+        grep_binary: cmd.grep_binary,
         highlight: cmd.highlight,
         icons: cmd.icons,
+        ignore_case: cmd.ignore_case,
         print_permissions: cmd.print_permissions,
         last_modify: cmd.last_modify,
         level: cmd.level,
+        match_dirs: cmd.match_dirs,
+        match_files: cmd.match_files,
+        match_path: cmd.match_path,
+        max_size: cmd.max_size,
+        min_depth: cmd.min_depth,
+        min_size: cmd.min_size,
+        // The most recent bound is the one that matters
+        newer: [cmd.newer, cmd.changed_within, cmd.newer_than]
+            .into_iter()
+            .flatten()
+            .max(),
         no_indent: cmd.no_indent,
+        older: cmd.older,
+        output_format: output_format(cmd),
         print_size: cmd.print_size,
         patterns: glob_patterns,
+        prune: cmd.prune,
+        regex_patterns,
         sort_by_time: cmd.sort_by_time,
+        uid: cmd.uid,
+        where_expr,
     })
 }
 
 pub fn run() -> io::Result<()> {
     let cmd = Cmd::parse();
-    let opts = cmd_to_opts(&cmd).map_err(|e| {
-        io::Error::new(io::ErrorKind::InvalidInput, format!("Invalid pattern: {e}"))
-    })?;
+    let opts = cmd_to_opts(&cmd).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
 
     let root_path = Path::new(&cmd.path);
     print_tree(root_path, &opts)
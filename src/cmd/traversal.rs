@@ -1,15 +1,21 @@
 use std::{
-    collections::HashSet,
-    fs::{DirEntry, read_dir},
+    collections::{HashMap, HashSet},
+    fs::{DirEntry, Metadata, read_dir},
     io::{BufWriter, Result, Write, stdout},
-    os::unix::fs::PermissionsExt,
+    os::unix::fs::{FileTypeExt, MetadataExt, PermissionsExt},
     path::Path,
     time::SystemTime,
 };
 
+use glob::{MatchOptions, Pattern};
+use regex::Regex;
+
 use crate::cmd::{
-    display::{format_date, format_entry_line, format_permissions},
-    root::Opts,
+    expr::Subject,
+    gitignore::Gitignore,
+    grep::{GrepMatch, search_file},
+    render::{Entry, Render, new_renderer},
+    root::{EntryType, Opts},
 };
 
 struct EntryInfo {
@@ -17,7 +23,121 @@ struct EntryInfo {
     last_modify: Result<SystemTime>,
 }
 
-fn check_valid_entry(path: &Path, name: Option<&str>, opts: &Opts, depth: usize) -> bool {
+/// State shared by the pre-process and the traversal of a tree.
+struct Context<'a> {
+    opts: &'a Opts,
+    root: &'a Path,
+    gitignore: Option<Gitignore>,
+}
+
+fn has_patterns(opts: &Opts) -> bool {
+    !opts.patterns.is_empty() || !opts.regex_patterns.is_empty()
+}
+
+/// Filters that only files can pass, directories are displayed for their descendants.
+fn has_file_filters(opts: &Opts) -> bool {
+    (!opts.entry_types.is_empty() && !opts.entry_types.contains(&EntryType::Directory))
+        || opts.grep.is_some()
+        || opts.min_size.is_some()
+        || opts.max_size.is_some()
+        || opts.newer.is_some()
+        || opts.older.is_some()
+}
+
+/// Entries are filtered by the pre-process when something must be known about their descendants.
+fn use_pre_process(opts: &Opts) -> bool {
+    has_patterns(opts)
+        || has_file_filters(opts)
+        || opts.prune
+        || has_owner_filters(opts)
+        || opts.where_expr.is_some()
+}
+
+fn has_owner_filters(opts: &Opts) -> bool {
+    opts.uid.is_some() || opts.gid.is_some()
+}
+
+fn is_owned(metadata: &Metadata, opts: &Opts) -> bool {
+    opts.uid.is_none_or(|uid| metadata.uid() == uid)
+        && opts.gid.is_none_or(|gid| metadata.gid() == gid)
+}
+
+fn is_entry_type(metadata: &Metadata, entry_type: EntryType) -> bool {
+    let file_type = metadata.file_type();
+    match entry_type {
+        EntryType::File => file_type.is_file(),
+        EntryType::Directory => file_type.is_dir(),
+        EntryType::Symlink => file_type.is_symlink(),
+        EntryType::Executable => file_type.is_file() && metadata.permissions().mode() & 0o111 != 0,
+        EntryType::Fifo => file_type.is_fifo(),
+        EntryType::Socket => file_type.is_socket(),
+        EntryType::BlockDevice => file_type.is_block_device(),
+        EntryType::CharDevice => file_type.is_char_device(),
+    }
+}
+
+/// Type, owner, size and modification time filters of files.
+fn check_file_metadata(metadata: &Metadata, opts: &Opts) -> bool {
+    if !is_owned(metadata, opts) {
+        return false;
+    }
+
+    if !opts.entry_types.is_empty()
+        && !opts
+            .entry_types
+            .iter()
+            .any(|&entry_type| is_entry_type(metadata, entry_type))
+    {
+        return false;
+    }
+
+    let size = metadata.len();
+    if opts.min_size.is_some_and(|min_size| size < min_size)
+        || opts.max_size.is_some_and(|max_size| size > max_size)
+    {
+        return false;
+    }
+
+    if opts.newer.is_some() || opts.older.is_some() {
+        let Ok(last_modify) = metadata.modified() else {
+            return false;
+        };
+        if opts.newer.is_some_and(|newer| last_modify <= newer)
+            || opts.older.is_some_and(|older| last_modify >= older)
+        {
+            return false;
+        }
+    }
+    true
+}
+
+/// What patterns are matched against: the entry name, or its path relative to the root with --match-path.
+fn match_target<'p>(path: &'p Path, name: Option<&'p str>, ctx: &Context) -> Option<&'p str> {
+    if ctx.opts.match_path {
+        path.strip_prefix(ctx.root)
+            .ok()
+            .and_then(|relative| relative.to_str())
+    } else {
+        name
+    }
+}
+
+/// Regexes are expected to be built case-insensitive already with --ignore-case.
+fn matches_any(target: &str, globs: &[Pattern], regexes: &[Regex], opts: &Opts) -> bool {
+    let match_options = MatchOptions {
+        case_sensitive: !opts.ignore_case,
+        // `*` stops at `/` when matching paths, `**` crosses directories
+        require_literal_separator: opts.match_path,
+        ..MatchOptions::new()
+    };
+    globs
+        .iter()
+        .any(|pattern| pattern.matches_with(target, match_options))
+        || regexes.iter().any(|regex| regex.is_match(target))
+}
+
+fn check_valid_entry(path: &Path, name: Option<&str>, ctx: &Context, depth: usize) -> bool {
+    let opts = ctx.opts;
     let is_hidden = name.map(|name| name.starts_with('.')).unwrap_or(false);
     if !opts.show_hidden && is_hidden {
         return false;
@@ -33,42 +153,73 @@ fn check_valid_entry(path: &Path, name: Option<&str>, opts: &Opts, depth: usize)
         return false;
     }
 
-    for exclude_pattern in opts.exclude_patterns.iter() {
-        if name.is_some_and(|name| exclude_pattern.matches(name)) {
-            return false;
-        }
+    if match_target(path, name, ctx).is_some_and(|target| {
+        matches_any(
+            target,
+            &opts.exclude_patterns,
+            &opts.exclude_regex_patterns,
+            opts,
+        )
+    }) {
+        return false;
+    }
+
+    // --type with d isn't a file filter, but still rejects the other types
+    if (has_file_filters(opts) || has_owner_filters(opts) || !opts.entry_types.is_empty())
+        && let Ok(metadata) = path.symlink_metadata()
+        && !metadata.is_dir()
+        && !check_file_metadata(&metadata, opts)
+    {
+        return false;
+    }
+
+    if let Some(gitignore) = &ctx.gitignore
+        && gitignore.is_ignored(path, path.is_dir())
+    {
+        return false;
     }
     true
 }
 
+#[allow(clippy::too_many_arguments)]
 fn pre_process_tree(
     root: &DirEntry,
-    opts: &Opts,
+    ctx: &Context,
     depth: usize,
     has_ancestors_matched: bool,
     display_entries: &mut HashSet<String>,
-    highlight_entries: &mut HashSet<String>,
+    matched_entries: &mut HashSet<String>,
+    grep_matches: &mut HashMap<String, GrepMatch>,
+    filelimits: &mut HashMap<String, usize>,
 ) -> bool {
+    let opts = ctx.opts;
     let path = root.path();
     let name = path.file_name().and_then(|name| name.to_str());
 
-    if !check_valid_entry(&path, name, opts, depth) {
+    if !check_valid_entry(&path, name, ctx, depth) {
         return false;
     }
 
     let mut should_display = true;
     let mut this_dir_matches = has_ancestors_matched;
 
-    if !opts.patterns.is_empty() {
-        for pattern in opts.patterns.iter() {
-            if name.is_some_and(|name| pattern.matches(name)) {
-                // if current entry matched pattern => highlight current entry
-                this_dir_matches = true;
-                if opts.highlight {
-                    highlight_entries.insert(path.display().to_string());
-                }
-                break;
-            }
+    if has_patterns(opts) {
+        // --match-dirs and --match-files restrict which entries patterns apply to
+        let can_match = if opts.match_dirs {
+            path.is_dir()
+        } else if opts.match_files {
+            !path.is_dir()
+        } else {
+            true
+        };
+        if can_match
+            && match_target(&path, name, ctx).is_some_and(|target| {
+                matches_any(target, &opts.patterns, &opts.regex_patterns, opts)
+            })
+        {
+            // if current entry matched pattern => mark it as matched (highlighted with --highlight)
+            this_dir_matches = true;
+            matched_entries.insert(path.display().to_string());
         }
         if !this_dir_matches {
             // if name is not match any patterns but has an ancestor that matched => still display
@@ -76,56 +227,135 @@ fn pre_process_tree(
         }
     }
 
-    if path.is_dir()
-        && let read_dir = read_dir(&path)
-        && let Ok(reader) = read_dir
+    if let Some(where_expr) = &opts.where_expr {
+        // Entries that don't satisfy the expression are displayed only for their descendants
+        let match_options = MatchOptions {
+            case_sensitive: !opts.ignore_case,
+            require_literal_separator: true,
+            ..MatchOptions::new()
+        };
+        let relative_path = path.strip_prefix(ctx.root).unwrap_or(&path);
+        should_display = should_display
+            && root.metadata().is_ok_and(|metadata| {
+                let subject = Subject {
+                    name: name.unwrap_or_default(),
+                    path: &relative_path.to_string_lossy(),
+                    metadata: &metadata,
+                    depth,
+                };
+                where_expr.matches(&subject, match_options)
+            });
+    }
+
+    // Directories at the -L limit are not opened, so they can't be known to be empty
+    let is_pruned = opts.prune && opts.level.is_none_or(|level| depth < level as usize);
+    // Directories of another owner are displayed like the ancestors of a -P match
+    let is_other_owner = has_owner_filters(opts)
+        && root
+            .metadata()
+            .is_ok_and(|metadata| !is_owned(&metadata, opts));
+    // Symlinks to directories are not followed, so they are checked as links
+    let is_dir = root.file_type().is_ok_and(|file_type| file_type.is_dir());
+    let children: Vec<DirEntry> = if is_dir && let Ok(reader) = read_dir(&path) {
+        reader.filter_map(Result::ok).collect()
+    } else {
+        Vec::new()
+    };
+    // Directories over --filelimit are not opened, like those at the -L limit
+    let exceeds_filelimit = if let Some(limit) = opts.filelimit
+        && is_dir
+        && depth >= opts.min_depth.map_or(1, |min_depth| min_depth as usize)
+    {
+        let count = children
+            .iter()
+            .filter(|child| {
+                let child_path = child.path();
+                let child_name = child_path.file_name().and_then(|name| name.to_str());
+                check_valid_entry(&child_path, child_name, ctx, depth + 1)
+            })
+            .count();
+        if count > limit {
+            filelimits.insert(path.display().to_string(), count);
+        }
+        count > limit
+    } else {
+        false
+    };
+    if (has_file_filters(opts) || is_pruned || is_other_owner) && is_dir {
+        // Directories are displayed only if one of their descendants passes the filters
+        should_display = false;
+    }
+    if exceeds_filelimit {
+        // Their contents are never searched, so they may hold matches of any filter
+        should_display = true;
+    }
+
+    if let Some(regex) = &opts.grep
+        && !is_dir
     {
-        reader.filter_map(Result::ok).for_each(|dir| {
+        // Only regular files can match by content
+        should_display = should_display
+            && root.file_type().is_ok_and(|file_type| file_type.is_file())
+            && match search_file(&path, regex, opts.grep_binary) {
+                Some(grep_match) => {
+                    grep_matches.insert(path.display().to_string(), grep_match);
+                    true
+                }
+                None => false,
+            };
+    }
+
+    if !exceeds_filelimit {
+        children.iter().for_each(|dir| {
             // if descendants are matched pattern => still display
             should_display |= pre_process_tree(
-                &dir,
-                opts,
+                dir,
+                ctx,
                 depth + 1,
                 this_dir_matches,
                 display_entries,
-                highlight_entries,
+                matched_entries,
+                grep_matches,
+                filelimits,
             );
         });
     }
 
-    if should_display && !opts.patterns.is_empty() {
+    if should_display && use_pre_process(opts) {
         display_entries.insert(path.display().to_string());
     }
 
     should_display
 }
 
-#[allow(clippy::too_many_arguments)]
-fn traverse_directory(
-    writer: &mut dyn Write,
+fn is_displayed(
+    entry: &DirEntry,
+    ctx: &Context,
+    display_entries: &HashSet<String>,
+    depth: usize,
+) -> bool {
+    if !use_pre_process(ctx.opts) {
+        // Without filters that depend on descendants, entries are checked on their own
+        let path = entry.path();
+        let name = path.file_name().and_then(|name| name.to_str());
+        check_valid_entry(&path, name, ctx, depth)
+    } else {
+        // Use pre-process set to filter
+        display_entries.contains(&entry.path().display().to_string())
+    }
+}
+
+/// Reads the displayed entries of a directory, directories first.
+fn read_entries(
     path: &Path,
-    opts: &Opts,
+    ctx: &Context,
     display_entries: &HashSet<String>,
-    highlight_entries: &HashSet<String>,
     depth: usize,
-    first_matched_ancestor: usize,
-    stats: &mut (u64, u64),
-    indent_state: &[bool],
-) -> Result<()> {
-    let mut entries_info: Vec<EntryInfo> = read_dir(path)?
+) -> Result<Vec<EntryInfo>> {
+    let entries_info: Vec<EntryInfo> = read_dir(path)?
         .filter_map(Result::ok)
-        .filter(|entry| {
-            if opts.patterns.is_empty() {
-                // If no -P is specified, don't use pre-process
-                // Child of current directory => depth + 1
-                let path = entry.path();
-                let name = path.file_name().and_then(|name| name.to_str());
-                check_valid_entry(&path, name, opts, depth + 1)
-            } else {
-                // Use pre-process set to filter
-                display_entries.contains(&entry.path().display().to_string())
-            }
-        })
+        // Child of current directory => depth + 1
+        .filter(|entry| is_displayed(entry, ctx, display_entries, depth + 1))
         .map(|entry| {
             let last_modify = entry.metadata().and_then(|m| m.modified());
             if let Err(e) = &last_modify {
@@ -139,72 +369,152 @@ fn traverse_directory(
         })
         .collect();
 
-    let (mut dirs, mut files): (Vec<EntryInfo>, Vec<EntryInfo>) = std::mem::take(&mut entries_info)
-        .into_iter()
-        .partition(|info| {
+    Ok(sort_entries(entries_info, ctx.opts))
+}
+
+/// Sorts entries by path (or time with -t), directories first.
+fn sort_entries(entries_info: Vec<EntryInfo>, opts: &Opts) -> Vec<EntryInfo> {
+    let (mut dirs, mut files): (Vec<EntryInfo>, Vec<EntryInfo>) =
+        entries_info.into_iter().partition(|info| {
             info.entry
                 .file_type()
                 .map(|ft| ft.is_dir())
                 .unwrap_or(false)
         });
 
+    // Entries of the level collapsed by --min-depth have different parents, so paths are compared
     let sort_comparison = |a: &EntryInfo, b: &EntryInfo| {
         if opts.sort_by_time {
             let time_a = a.last_modify.as_ref().unwrap_or(&SystemTime::UNIX_EPOCH);
             let time_b = b.last_modify.as_ref().unwrap_or(&SystemTime::UNIX_EPOCH);
             time_a
                 .cmp(time_b)
-                .then_with(|| a.entry.file_name().cmp(&b.entry.file_name()))
+                .then_with(|| a.entry.path().cmp(&b.entry.path()))
         } else {
-            a.entry.file_name().cmp(&b.entry.file_name())
+            a.entry.path().cmp(&b.entry.path())
         }
     };
 
     dirs.sort_unstable_by(sort_comparison);
     files.sort_unstable_by(sort_comparison);
 
-    entries_info.append(&mut dirs);
-    entries_info.append(&mut files);
+    dirs.append(&mut files);
+    dirs
+}
 
+/// Entries at --min-depth below a directory, the levels above them are collapsed.
+fn read_min_depth_entries(
+    path: &Path,
+    ctx: &Context,
+    display_entries: &HashSet<String>,
+    depth: usize,
+    min_depth: usize,
+    entries_info: &mut Vec<EntryInfo>,
+) -> Result<()> {
+    for info in read_entries(path, ctx, display_entries, depth)? {
+        if depth + 1 >= min_depth {
+            entries_info.push(info);
+        } else if info.entry.file_type()?.is_dir() {
+            read_min_depth_entries(
+                &info.entry.path(),
+                ctx,
+                display_entries,
+                depth + 1,
+                min_depth,
+                entries_info,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn traverse_directory(
+    renderer: &mut dyn Render,
+    entries_info: Vec<EntryInfo>,
+    ctx: &Context,
+    display_entries: &HashSet<String>,
+    matched_entries: &HashSet<String>,
+    grep_matches: &HashMap<String, GrepMatch>,
+    filelimits: &HashMap<String, usize>,
+    depth: usize,
+    first_matched_ancestor: usize,
+    stats: &mut (u64, u64),
+    indent_state: &[bool],
+) -> Result<()> {
+    let opts = ctx.opts;
     let last_idx = entries_info.len().saturating_sub(1);
     for (idx, info) in entries_info.into_iter().enumerate() {
         let entry = info.entry;
         let path = entry.path();
         let is_last_entry = idx == last_idx;
-        let should_highlight = highlight_entries.contains(&path.display().to_string());
+        let path_str = path.display().to_string();
+        let is_matched = matched_entries.contains(&path_str);
+        let should_highlight = opts.highlight && is_matched;
+        // Levels are counted from the first displayed one, which is not the root with --min-depth
         let first_matched_ancestor = if should_highlight {
-            first_matched_ancestor.min(depth)
+            first_matched_ancestor.min(indent_state.len())
         } else {
             first_matched_ancestor
         };
 
-        let line = format_entry_line(
-            &entry,
-            opts,
+        let is_dir = entry.file_type()?.is_dir();
+        // Directories are read before being rendered, to know if they exceed --filelimit
+        let children = (is_dir && !filelimits.contains_key(&path_str))
+            .then(|| read_entries(&path, ctx, display_entries, depth + 1));
+        let filelimit = filelimits.get(&path_str).copied().or_else(|| {
+            let count = children.as_ref()?.as_ref().ok()?.len();
+            opts.filelimit.filter(|&limit| count > limit).map(|_| count)
+        });
+
+        // Entries of the first displayed level are prefixed with their collapsed ancestors
+        let prefix = if indent_state.is_empty() && depth > 0 {
+            path.parent()
+                .and_then(|parent| parent.strip_prefix(ctx.root).ok())
+        } else {
+            None
+        };
+
+        let node = Entry {
+            dir_entry: &entry,
+            depth: depth + 1,
+            level: indent_state.len() + 1,
             indent_state,
-            is_last_entry,
-            should_highlight,
+            is_last: is_last_entry,
+            matched: is_matched,
+            highlight: should_highlight,
             first_matched_ancestor,
-        )?;
+            grep_match: grep_matches.get(&path_str),
+            filelimit,
+            prefix,
+        };
 
-        writeln!(writer, "{line}")?;
+        renderer.entry(&node)?;
 
-        if entry.file_type()?.is_dir() {
+        if is_dir {
             stats.0 += 1;
 
-            let mut next_indent_state = indent_state.to_vec();
-            next_indent_state.push(is_last_entry);
-            traverse_directory(
-                writer,
-                &path,
-                opts,
-                display_entries,
-                highlight_entries,
-                depth + 1,
-                first_matched_ancestor,
-                stats,
-                &next_indent_state,
-            )?;
+            // Directories with too many entries are listed without their contents
+            if filelimit.is_none()
+                && let Some(children) = children
+            {
+                let mut next_indent_state = indent_state.to_vec();
+                next_indent_state.push(is_last_entry);
+                traverse_directory(
+                    renderer,
+                    children?,
+                    ctx,
+                    display_entries,
+                    matched_entries,
+                    grep_matches,
+                    filelimits,
+                    depth + 1,
+                    first_matched_ancestor,
+                    stats,
+                    &next_indent_state,
+                )?;
+            }
+            renderer.leave_dir(&node)?;
         } else {
             stats.1 += 1;
         }
@@ -219,46 +529,21 @@ pub fn print_tree(path: &Path, opts: &Opts) -> Result<()> {
 
 pub fn print_tree_with_writer(path: &Path, opts: &Opts, writer: &mut dyn Write) -> Result<()> {
     let metadata = std::fs::metadata(path)?;
-    let mut display_path = String::new();
-
-    if opts.print_permissions {
-        let mode = metadata.permissions().mode();
-        let perms_str = format_permissions(mode, metadata.file_type().is_dir());
-        display_path.push_str(&perms_str);
-        display_path.push(' ');
-    }
-
-    if opts.last_modify {
-        match metadata.modified() {
-            Ok(mod_time) => {
-                let date_str = format!("[{}] ", format_date(mod_time));
-                display_path.push_str(&date_str);
-            }
-            Err(e) => {
-                eprintln!(
-                    "Warning: Could not get modification date for {:?}: {}",
-                    path, e
-                );
-            }
-        }
-    }
+    let mut renderer = new_renderer(opts, writer);
+    renderer.begin(path, &metadata)?;
 
-    if opts.full_path {
-        display_path.push_str(&path.canonicalize()?.display().to_string());
-    } else {
-        display_path.push_str(
-            path.file_name()
-                .and_then(|name| name.to_str())
-                .unwrap_or("."),
-        );
+    let ctx = Context {
+        opts,
+        root: path,
+        gitignore: opts.gitignore.then(|| Gitignore::new(path)),
     };
 
-    writeln!(writer, "{display_path}")?;
-
     let mut display_entries = HashSet::new();
-    let mut highlight_entries = HashSet::new();
-    // Pre-process if -P is specified
-    if !opts.patterns.is_empty() {
+    let mut matched_entries = HashSet::new();
+    let mut grep_matches = HashMap::new();
+    let mut filelimits = HashMap::new();
+    // Pre-process when some entries are displayed only for their descendants
+    if use_pre_process(opts) {
         match read_dir(path) {
             Ok(reader) => reader,
             Err(e) => {
@@ -270,39 +555,46 @@ pub fn print_tree_with_writer(path: &Path, opts: &Opts, writer: &mut dyn Write)
         .for_each(|entry| {
             pre_process_tree(
                 &entry,
-                opts,
+                &ctx,
                 1,
                 false,
                 &mut display_entries,
-                &mut highlight_entries,
+                &mut matched_entries,
+                &mut grep_matches,
+                &mut filelimits,
             );
         });
     }
 
     let mut stats = (0, 0); // (dirs, files)
 
-    traverse_directory(
-        writer,
+    // With --min-depth, the shallower levels are collapsed into the names of the first displayed one
+    let min_depth = opts
+        .min_depth
+        .map_or(1, |min_depth| min_depth.max(1) as usize);
+    let mut entries_info = Vec::new();
+    read_min_depth_entries(
         path,
-        opts,
+        &ctx,
         &display_entries,
-        &highlight_entries,
         0,
+        min_depth,
+        &mut entries_info,
+    )?;
+
+    traverse_directory(
+        renderer.as_mut(),
+        sort_entries(entries_info, opts),
+        &ctx,
+        &display_entries,
+        &matched_entries,
+        &grep_matches,
+        &filelimits,
+        min_depth - 1,
         usize::MAX,
         &mut stats,
         &[],
     )?;
 
-    let dir_str = if stats.0 == 1 {
-        "directory"
-    } else {
-        "directories"
-    };
-    let file_str = if stats.1 == 1 { "file" } else { "files" };
-    writeln!(
-        writer,
-        "\n{} {}, {} {}",
-        stats.0, dir_str, stats.1, file_str
-    )?;
-    Ok(())
+    renderer.end(stats)
 }
@@ -0,0 +1,27 @@
+use std::fs::read_to_string;
+
+const PASSWD_FILE: &str = "/etc/passwd";
+const GROUP_FILE: &str = "/etc/group";
+
+/// Looks up a name in a colon separated database like /etc/passwd, where the id is the third field.
+fn lookup_id(database: &str, name: &str) -> Option<u32> {
+    let content = read_to_string(database).ok()?;
+    content
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .find_map(|line| {
+            let mut fields = line.split(':');
+            if fields.next()? != name {
+                return None;
+            }
+            fields.nth(1)?.parse().ok()
+        })
+}
+
+pub fn lookup_uid(name: &str) -> Option<u32> {
+    lookup_id(PASSWD_FILE, name)
+}
+
+pub fn lookup_gid(name: &str) -> Option<u32> {
+    lookup_id(GROUP_FILE, name)
+}
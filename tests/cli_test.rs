@@ -1,6 +1,17 @@
+#![allow(clippy::field_reassign_with_default)]
+
 use std::path::Path;
 
-use treer::cmd::{root::Opts, traversal::print_tree_with_writer};
+use clap::{CommandFactory, Parser};
+use treer::cmd::{
+    root::{Cmd, Opts},
+    traversal::print_tree_with_writer,
+};
+
+#[test]
+fn test_cmd_definition() {
+    Cmd::command().debug_assert();
+}
 
 #[test]
 fn test_print_default() {
@@ -219,3 +230,717 @@ fn test_combination() {
 ";
     assert_eq!(result, expected);
 }
+
+fn create_tree(name: &str, files: &[(&str, &str)]) -> std::path::PathBuf {
+    let root = std::env::temp_dir().join(format!("treer-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    for (file, content) in files {
+        let path = root.join(file);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+    root
+}
+
+#[test]
+fn test_gitignore() {
+    let root = create_tree(
+        "gitignore",
+        &[
+            (".gitignore", "*.log\nbuild/\n!keep.log\n/docs/generated\n"),
+            ("a.log", ""),
+            ("keep.log", ""),
+            ("main.rs", ""),
+            ("build/out.txt", ""),
+            ("docs/generated/api.md", ""),
+            ("docs/guide.md", ""),
+            ("sub/.gitignore", "!*.log\nsecret.txt\n"),
+            ("sub/b.log", ""),
+            ("sub/secret.txt", ""),
+            ("sub/generated/c.md", ""),
+        ],
+    );
+    let mut opts: Opts = Default::default();
+    opts.gitignore = true;
+
+    let mut buffer = Vec::new();
+    let _ = print_tree_with_writer(&root, &opts, &mut buffer);
+
+    let result = String::from_utf8(buffer).expect("Not valid UTF-8");
+    let expected = format!(
+        "{}
+├── docs
+│   └── guide.md
+├── sub
+│   ├── generated
+│   │   └── c.md
+│   └── b.log
+├── keep.log
+└── main.rs
+
+3 directories, 5 files
+",
+        root.file_name().unwrap().to_str().unwrap()
+    );
+    assert_eq!(result, expected);
+
+    opts.patterns = vec![glob::Pattern::new("*.md").unwrap()];
+    buffer = Vec::new();
+    let _ = print_tree_with_writer(&root, &opts, &mut buffer);
+
+    let result = String::from_utf8(buffer).expect("Not valid UTF-8");
+    let expected = format!(
+        "{}
+├── docs
+│   └── guide.md
+└── sub
+    └── generated
+        └── c.md
+
+3 directories, 2 files
+",
+        root.file_name().unwrap().to_str().unwrap()
+    );
+    assert_eq!(result, expected);
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[test]
+fn test_regex_patterns() {
+    let path = Path::new("tests/sample-directory");
+    let mut opts: Opts = Default::default();
+    opts.regex_patterns = vec![regex::Regex::new(r"^file[0-9]\.(md|toml)$").unwrap()];
+
+    let mut buffer = Vec::new();
+    let _ = print_tree_with_writer(path, &opts, &mut buffer);
+
+    let result = String::from_utf8(buffer).expect("Not valid UTF-8");
+    let expected = "sample-directory
+├── sub-dir-lv1
+│   └── file3.toml
+└── file1.md
+
+1 directory, 2 files
+";
+    assert_eq!(result, expected);
+
+    opts.regex_patterns = vec![];
+    opts.exclude_regex_patterns = vec![regex::Regex::new(r"lv2|\.txt$").unwrap()];
+    buffer = Vec::new();
+    let _ = print_tree_with_writer(path, &opts, &mut buffer);
+
+    let result = String::from_utf8(buffer).expect("Not valid UTF-8");
+    let expected = "sample-directory
+├── sub-dir-lv1
+│   └── file3.toml
+└── file1.md
+
+1 directory, 2 files
+";
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_ignore_case() {
+    let path = Path::new("tests/sample-directory");
+    let mut opts: Opts = Default::default();
+    opts.patterns = vec![glob::Pattern::new("FILE[13]*").unwrap()];
+    opts.exclude_patterns = vec![glob::Pattern::new("SUB-DIR-LV2").unwrap()];
+
+    let mut buffer = Vec::new();
+    let _ = print_tree_with_writer(path, &opts, &mut buffer);
+
+    let result = String::from_utf8(buffer).expect("Not valid UTF-8");
+    let expected = "sample-directory
+
+0 directories, 0 files
+";
+    assert_eq!(result, expected);
+
+    opts.ignore_case = true;
+    buffer = Vec::new();
+    let _ = print_tree_with_writer(path, &opts, &mut buffer);
+
+    let result = String::from_utf8(buffer).expect("Not valid UTF-8");
+    let expected = "sample-directory
+├── sub-dir-lv1
+│   └── file3.toml
+└── file1.md
+
+1 directory, 2 files
+";
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_match_path() {
+    let path = Path::new("tests/sample-directory");
+    let mut opts: Opts = Default::default();
+    opts.match_path = true;
+    opts.exclude_patterns = vec![glob::Pattern::new("sub-dir-lv1/sub-dir-lv2/*").unwrap()];
+
+    let mut buffer = Vec::new();
+    let _ = print_tree_with_writer(path, &opts, &mut buffer);
+
+    let result = String::from_utf8(buffer).expect("Not valid UTF-8");
+    let expected = "sample-directory
+├── sub-dir-lv1
+│   ├── sub-dir-lv2
+│   └── file3.toml
+├── file1.md
+└── file2.txt
+
+2 directories, 3 files
+";
+    assert_eq!(result, expected);
+
+    opts.exclude_patterns = vec![];
+    opts.patterns = vec![glob::Pattern::new("**/sub-dir-lv3").unwrap()];
+    buffer = Vec::new();
+    let _ = print_tree_with_writer(path, &opts, &mut buffer);
+
+    let result = String::from_utf8(buffer).expect("Not valid UTF-8");
+    let expected = "sample-directory
+└── sub-dir-lv1
+    └── sub-dir-lv2
+        └── sub-dir-lv3
+            └── file5.abc
+
+3 directories, 1 file
+";
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_grep_annotate_sanitized() {
+    let long_line = format!("match {}", "x".repeat(100));
+    let root = create_tree(
+        "grep-sanitized",
+        &[("escape", "foo \x1b[31mRED\tend\n"), ("long", &long_line)],
+    );
+    let mut opts: Opts = Default::default();
+    opts.grep = Some(regex::Regex::new("RED|match").unwrap());
+    opts.grep_annotate = true;
+
+    let mut buffer = Vec::new();
+    let _ = print_tree_with_writer(&root, &opts, &mut buffer);
+
+    let result = String::from_utf8(buffer).expect("Not valid UTF-8");
+    let expected = format!(
+        "{}
+├── escape [1 match, line 1: foo ?[31mRED\tend]
+└── long [1 match, line 1: match {}...]
+
+0 directories, 2 files
+",
+        root.file_name().unwrap().to_string_lossy(),
+        "x".repeat(74)
+    );
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_grep() {
+    let root = create_tree(
+        "grep",
+        &[
+            ("src/main.rs", "fn main() {\n    todo!()\n}\n"),
+            ("src/lib.rs", "// TODO: docs\npub fn lib() {}\n"),
+            ("docs/guide.md", "Nothing to see\n"),
+            ("todo.bin", "\0todo!()\n"),
+        ],
+    );
+    let mut opts: Opts = Default::default();
+    opts.grep = Some(regex::Regex::new("todo").unwrap());
+    opts.grep_annotate = true;
+
+    let mut buffer = Vec::new();
+    let _ = print_tree_with_writer(&root, &opts, &mut buffer);
+
+    let result = String::from_utf8(buffer).expect("Not valid UTF-8");
+    let expected = format!(
+        "{}
+└── src
+    └── main.rs [1 match, line 2: todo!()]
+
+1 directory, 1 file
+",
+        root.file_name().unwrap().to_string_lossy()
+    );
+    assert_eq!(result, expected);
+
+    opts.grep = Some(
+        regex::RegexBuilder::new("todo")
+            .case_insensitive(true)
+            .build()
+            .unwrap(),
+    );
+    opts.grep_annotate = false;
+    opts.grep_binary = true;
+    opts.patterns = vec![
+        glob::Pattern::new("*.rs").unwrap(),
+        glob::Pattern::new("*.bin").unwrap(),
+    ];
+    buffer = Vec::new();
+    let _ = print_tree_with_writer(&root, &opts, &mut buffer);
+
+    let result = String::from_utf8(buffer).expect("Not valid UTF-8");
+    let expected = format!(
+        "{}
+├── src
+│   ├── lib.rs
+│   └── main.rs
+└── todo.bin
+
+1 directory, 3 files
+",
+        root.file_name().unwrap().to_string_lossy()
+    );
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_size_filters() {
+    let path = Path::new("tests/sample-directory");
+    let mut opts: Opts = Default::default();
+    opts.show_hidden = true;
+    opts.min_size = Some(31);
+
+    let mut buffer = Vec::new();
+    let _ = print_tree_with_writer(path, &opts, &mut buffer);
+
+    let result = String::from_utf8(buffer).expect("Not valid UTF-8");
+    let expected = "sample-directory
+└── .hidden.lock
+
+0 directories, 1 file
+";
+    assert_eq!(result, expected);
+
+    opts.min_size = None;
+    opts.max_size = Some(0);
+    buffer = Vec::new();
+    let _ = print_tree_with_writer(path, &opts, &mut buffer);
+
+    let result = String::from_utf8(buffer).expect("Not valid UTF-8");
+    let expected = "sample-directory
+└── sub-dir-lv1
+    └── sub-dir-lv2
+        ├── sub-dir-lv3
+        │   └── file5.abc
+        └── .hidden2.txt
+
+3 directories, 2 files
+";
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_parse_size() {
+    let parse = |size: &str| {
+        Cmd::try_parse_from(["treer", "--min-size", size])
+            .map(|cmd| cmd.min_size)
+            .ok()
+    };
+    assert_eq!(parse("512"), Some(Some(512)));
+    assert_eq!(parse("10K"), Some(Some(10 * 1024)));
+    assert_eq!(parse("1.5MiB"), Some(Some(3 * 512 * 1024)));
+    assert_eq!(parse("2KB"), Some(Some(2048)));
+    assert_eq!(parse("2kB"), Some(Some(2000)));
+    assert_eq!(parse("1.5mB"), Some(Some(1_500_000)));
+    assert_eq!(parse("1G"), Some(Some(1 << 30)));
+    assert_eq!(parse("1x"), None);
+    assert_eq!(parse("K"), None);
+    assert_eq!(parse("18446744073709551616000"), None);
+    assert_eq!(parse("99999999T"), None);
+}
+
+#[test]
+fn test_time_filters() {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    let root = create_tree(
+        "time",
+        &[("old/a.txt", ""), ("new/b.txt", ""), ("c.txt", "")],
+    );
+    let set_modified = |file: &str, secs: u64| {
+        std::fs::File::options()
+            .write(true)
+            .open(root.join(file))
+            .unwrap()
+            .set_modified(UNIX_EPOCH + Duration::from_secs(secs))
+            .unwrap();
+    };
+    set_modified("old/a.txt", 1_000);
+    set_modified("new/b.txt", 3_000);
+    set_modified("c.txt", 2_000);
+
+    let mut opts: Opts = Default::default();
+    opts.newer = Some(UNIX_EPOCH + Duration::from_secs(1_500));
+    opts.older = Some(UNIX_EPOCH + Duration::from_secs(2_500));
+
+    let mut buffer = Vec::new();
+    let _ = print_tree_with_writer(&root, &opts, &mut buffer);
+
+    let result = String::from_utf8(buffer).expect("Not valid UTF-8");
+    let expected = format!(
+        "{}
+└── c.txt
+
+0 directories, 1 file
+",
+        root.file_name().unwrap().to_string_lossy()
+    );
+    assert_eq!(result, expected);
+
+    let cmd = Cmd::try_parse_from([
+        "treer",
+        "--newer",
+        "2024-02-29 12:30",
+        "--older",
+        "1970-01-02",
+    ]);
+    let cmd = cmd.unwrap();
+    assert_eq!(
+        cmd.newer,
+        Some(UNIX_EPOCH + Duration::from_secs(1_709_209_800))
+    );
+    assert_eq!(cmd.older, Some(UNIX_EPOCH + Duration::from_secs(86_400)));
+    assert!(Cmd::try_parse_from(["treer", "--older", "2024-13-01"]).is_err());
+    assert!(Cmd::try_parse_from(["treer", "--older", "2024-02-31"]).is_err());
+    assert!(Cmd::try_parse_from(["treer", "--older", "2023-02-29"]).is_err());
+    assert!(Cmd::try_parse_from(["treer", "--older", "2024-04-31"]).is_err());
+    assert!(Cmd::try_parse_from(["treer", "--older", "2024-02-29"]).is_ok());
+    assert!(Cmd::try_parse_from(["treer", "--newer", "999999999999999-01-01"]).is_err());
+    assert!(Cmd::try_parse_from(["treer", "--changed-within", "99999999999999999w"]).is_err());
+    assert!(Cmd::try_parse_from(["treer", "--changed-within", "2x"]).is_err());
+}
+
+#[test]
+fn test_entry_types() {
+    use std::os::unix::fs::{PermissionsExt, symlink};
+    use treer::cmd::root::EntryType;
+
+    let root = create_tree(
+        "types",
+        &[("bin/run", ""), ("etc/conf", ""), ("empty/.keep", "")],
+    );
+    std::fs::set_permissions(root.join("bin/run"), std::fs::Permissions::from_mode(0o755)).unwrap();
+    symlink("bin/run", root.join("link")).unwrap();
+    symlink("etc", root.join("linkdir")).unwrap();
+
+    let mut opts: Opts = Default::default();
+    opts.entry_types = vec![EntryType::Executable, EntryType::Symlink];
+
+    let mut buffer = Vec::new();
+    let _ = print_tree_with_writer(&root, &opts, &mut buffer);
+
+    let result = String::from_utf8(buffer).expect("Not valid UTF-8");
+    let expected = format!(
+        "{}
+├── bin
+│   └── run
+├── link
+└── linkdir
+
+1 directory, 3 files
+",
+        root.file_name().unwrap().to_string_lossy()
+    );
+    assert_eq!(result, expected);
+
+    opts.entry_types = vec![EntryType::Directory, EntryType::Symlink];
+    buffer = Vec::new();
+    let _ = print_tree_with_writer(&root, &opts, &mut buffer);
+
+    let result = String::from_utf8(buffer).expect("Not valid UTF-8");
+    let expected = format!(
+        "{}
+├── bin
+├── empty
+├── etc
+├── link
+└── linkdir
+
+3 directories, 2 files
+",
+        root.file_name().unwrap().to_string_lossy()
+    );
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_prune() {
+    let path = Path::new("tests/sample-directory");
+    let mut opts: Opts = Default::default();
+    opts.show_hidden = true;
+    opts.prune = true;
+    opts.exclude_patterns = vec![glob::Pattern::new("file5*").unwrap()];
+
+    let mut buffer = Vec::new();
+    let _ = print_tree_with_writer(path, &opts, &mut buffer);
+
+    let result = String::from_utf8(buffer).expect("Not valid UTF-8");
+    let expected = "sample-directory
+├── sub-dir-lv1
+│   ├── sub-dir-lv2
+│   │   ├── .hidden2.txt
+│   │   └── file4
+│   └── file3.toml
+├── .hidden.lock
+├── file1.md
+└── file2.txt
+
+2 directories, 6 files
+";
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_filelimit() {
+    let path = Path::new("tests/sample-directory/sub-dir-lv1");
+    let mut opts: Opts = Default::default();
+    opts.filelimit = Some(1);
+
+    let mut buffer = Vec::new();
+    let _ = print_tree_with_writer(path, &opts, &mut buffer);
+
+    let result = String::from_utf8(buffer).expect("Not valid UTF-8");
+    let expected = "sub-dir-lv1
+├── sub-dir-lv2 [2 entries exceeds filelimit, not opening dir]
+└── file3.toml
+
+1 directory, 1 file
+";
+    assert_eq!(result, expected);
+
+    // Filtered trees are not searched below a directory over the limit
+    let root = create_tree("filelimit", &[("a/x", "needle"), ("a/y", "hay")]);
+    opts.grep = Some(regex::Regex::new("needle").unwrap());
+    buffer = Vec::new();
+    let _ = print_tree_with_writer(&root, &opts, &mut buffer);
+
+    let result = String::from_utf8(buffer).expect("Not valid UTF-8");
+    let expected = format!(
+        "{}
+└── a [2 entries exceeds filelimit, not opening dir]
+
+1 directory, 0 files
+",
+        root.file_name().unwrap().to_string_lossy()
+    );
+    assert_eq!(result, expected);
+
+    opts.grep = None;
+    opts.patterns = vec![glob::Pattern::new("x").unwrap()];
+    buffer = Vec::new();
+    let _ = print_tree_with_writer(&root, &opts, &mut buffer);
+
+    let result = String::from_utf8(buffer).expect("Not valid UTF-8");
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_owner_filters() {
+    use std::os::unix::fs::MetadataExt;
+
+    let root = create_tree("owner", &[("a/b/f1", ""), ("c/f2", "")]);
+    let uid = std::fs::metadata(root.join("a/b/f1")).unwrap().uid();
+    let mut opts: Opts = Default::default();
+    opts.uid = Some(uid);
+
+    let mut buffer = Vec::new();
+    let _ = print_tree_with_writer(&root, &opts, &mut buffer);
+
+    let result = String::from_utf8(buffer).expect("Not valid UTF-8");
+    let expected = format!(
+        "{}
+├── a
+│   └── b
+│       └── f1
+└── c
+    └── f2
+
+3 directories, 2 files
+",
+        root.file_name().unwrap().to_string_lossy()
+    );
+    assert_eq!(result, expected);
+
+    opts.uid = Some(uid.wrapping_add(1));
+    buffer = Vec::new();
+    let _ = print_tree_with_writer(&root, &opts, &mut buffer);
+
+    let result = String::from_utf8(buffer).expect("Not valid UTF-8");
+    let expected = format!(
+        "{}
+
+0 directories, 0 files
+",
+        root.file_name().unwrap().to_string_lossy()
+    );
+    assert_eq!(result, expected);
+
+    let cmd = Cmd::try_parse_from(["treer", "--user", "root", "--group", "0"]).unwrap();
+    assert_eq!((cmd.uid, cmd.gid), (Some(0), Some(0)));
+}
+
+#[test]
+fn test_where() {
+    let path = Path::new("tests/sample-directory");
+    let mut opts: Opts = Default::default();
+    opts.where_expr =
+        Some(treer::cmd::expr::Expr::parse("ext == toml or (type == file and depth > 2)").unwrap());
+
+    let mut buffer = Vec::new();
+    let _ = print_tree_with_writer(path, &opts, &mut buffer);
+
+    let result = String::from_utf8(buffer).expect("Not valid UTF-8");
+    let expected = "sample-directory
+└── sub-dir-lv1
+    ├── sub-dir-lv2
+    │   ├── sub-dir-lv3
+    │   │   └── file5.abc
+    │   └── file4
+    └── file3.toml
+
+3 directories, 3 files
+";
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_match_dirs_and_files() {
+    let root = create_tree("match-kind", &[("src/a/x.rs", ""), ("lib/src", "")]);
+    let mut opts: Opts = Default::default();
+    opts.patterns = vec![glob::Pattern::new("src").unwrap()];
+    opts.match_dirs = true;
+
+    let mut buffer = Vec::new();
+    let _ = print_tree_with_writer(&root, &opts, &mut buffer);
+
+    let result = String::from_utf8(buffer).expect("Not valid UTF-8");
+    let expected = format!(
+        "{}
+└── src
+    └── a
+        └── x.rs
+
+2 directories, 1 file
+",
+        root.file_name().unwrap().to_string_lossy()
+    );
+    assert_eq!(result, expected);
+
+    opts.match_dirs = false;
+    opts.match_files = true;
+    buffer = Vec::new();
+    let _ = print_tree_with_writer(&root, &opts, &mut buffer);
+
+    let result = String::from_utf8(buffer).expect("Not valid UTF-8");
+    let expected = format!(
+        "{}
+└── lib
+    └── src
+
+1 directory, 1 file
+",
+        root.file_name().unwrap().to_string_lossy()
+    );
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_pattern_files() {
+    let root = create_tree(
+        "pattern-files",
+        &[
+            (
+                "exclude.txt",
+                "# generated files\n\nfile[12]* \n  # indented [note\n\nsub-dir-lv3\t\r\n",
+            ),
+            ("invalid.txt", "file1.md\n[bad\n"),
+        ],
+    );
+    let treer = |args: &[&str]| {
+        std::process::Command::new(env!("CARGO_BIN_EXE_treer"))
+            .arg("tests/sample-directory")
+            .args(args)
+            .output()
+            .unwrap()
+    };
+
+    let exclude_file = root.join("exclude.txt");
+    let output = treer(&["--exclude-from", exclude_file.to_str().unwrap()]);
+    let expected = "sample-directory
+└── sub-dir-lv1
+    ├── sub-dir-lv2
+    │   └── file4
+    └── file3.toml
+
+2 directories, 2 files
+";
+    assert_eq!(String::from_utf8_lossy(&output.stdout), expected);
+
+    let invalid_file = root.join("invalid.txt");
+    let output = treer(&["--pattern-from", invalid_file.to_str().unwrap()]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains(&format!("Invalid pattern: {}:2:", invalid_file.display())));
+}
+
+#[test]
+fn test_min_depth() {
+    let root = create_tree(
+        "min-depth",
+        &[
+            ("crates/a/src/bin/x.rs", ""),
+            ("crates/a/src/lib.rs", ""),
+            ("crates/a/Cargo.toml", ""),
+            ("crates/b/src/main.rs", ""),
+            ("README", ""),
+        ],
+    );
+    let mut opts: Opts = Default::default();
+    opts.min_depth = Some(3);
+
+    let mut buffer = Vec::new();
+    let _ = print_tree_with_writer(&root, &opts, &mut buffer);
+
+    let result = String::from_utf8(buffer).expect("Not valid UTF-8");
+    let expected = format!(
+        "{}
+├── crates/a/src
+│   ├── bin
+│   │   └── x.rs
+│   └── lib.rs
+├── crates/b/src
+│   └── main.rs
+└── crates/a/Cargo.toml
+
+3 directories, 4 files
+",
+        root.file_name().unwrap().to_string_lossy()
+    );
+    assert_eq!(result, expected);
+
+    // Depths stay relative to the root with pre-processing
+    opts.patterns = vec![glob::Pattern::new("*.rs").unwrap()];
+    opts.level = Some(4);
+    buffer = Vec::new();
+    let _ = print_tree_with_writer(&root, &opts, &mut buffer);
+
+    let result = String::from_utf8(buffer).expect("Not valid UTF-8");
+    let expected = format!(
+        "{}
+├── crates/a/src
+│   └── lib.rs
+└── crates/b/src
+    └── main.rs
+
+2 directories, 2 files
+",
+        root.file_name().unwrap().to_string_lossy()
+    );
+    assert_eq!(result, expected);
+}
@@ -1,6 +1,8 @@
 use std::time::UNIX_EPOCH;
 
-use treer::cmd::display::{format_date, format_file_size, format_permissions};
+use treer::cmd::display::{
+    encode_url_path, escape_xml, format_date, format_file_size, format_iso_date, format_permissions,
+};
 
 #[test]
 fn test_format_permissions() {
@@ -42,3 +44,26 @@ fn test_format_date() {
     assert_eq!(format_date(date_4), "1986-11-18 08:31:06");
     assert_eq!(format_date(date_5), "1981-04-03 21:06:39");
 }
+
+#[test]
+fn test_escape_xml() {
+    assert_eq!(escape_xml("file.txt"), "file.txt");
+    assert_eq!(
+        escape_xml("<a & \"b\"> 'c'"),
+        "&lt;a &amp; &quot;b&quot;&gt; &apos;c&apos;"
+    );
+    assert_eq!(escape_xml("a\nb\u{1}"), "a&#10;b\u{fffd}");
+}
+
+#[test]
+fn test_encode_url_path() {
+    assert_eq!(encode_url_path("src/main.rs"), "src/main.rs");
+    assert_eq!(encode_url_path("my docs/a#b?.md"), "my%20docs/a%23b%3F.md");
+    assert_eq!(encode_url_path("café"), "caf%C3%A9");
+}
+
+#[test]
+fn test_format_iso_date() {
+    let date = UNIX_EPOCH + std::time::Duration::from_secs(69696969);
+    assert_eq!(format_iso_date(date), "1972-03-17T16:16:09Z");
+}
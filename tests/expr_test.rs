@@ -0,0 +1,81 @@
+use std::fs::metadata;
+
+use glob::MatchOptions;
+use treer::cmd::expr::{Expr, Subject};
+
+fn matches(expr: &str, name: &str, path: &str, depth: usize) -> bool {
+    let metadata = metadata("tests/sample-directory/file1.md").unwrap();
+    let subject = Subject {
+        name,
+        path,
+        metadata: &metadata,
+        depth,
+    };
+    Expr::parse(expr)
+        .unwrap()
+        .matches(&subject, MatchOptions::new())
+}
+
+#[test]
+fn test_expr_matches() {
+    assert!(matches("ext == md", "file1.md", "file1.md", 1));
+    assert!(matches(
+        "ext == \"md\" and size >= 30 and not path ~ \"tests/**\"",
+        "file1.md",
+        "src/file1.md",
+        2
+    ));
+    assert!(!matches(
+        "ext == md and not path ~ 'tests/**'",
+        "file1.md",
+        "tests/unit/file1.md",
+        3
+    ));
+    assert!(matches(
+        "(size > 1k or depth > 2) && type == file",
+        "file1.md",
+        "a/b/file1.md",
+        3
+    ));
+    assert!(matches(
+        "!(name ~ '*.rs') || size < 1",
+        "file1.md",
+        "file1.md",
+        1
+    ));
+    assert!(matches("ext == ''", ".hidden", ".hidden", 1));
+    assert!(matches(
+        "mtime > 1970-01-02 and mtime < 0s",
+        "file1.md",
+        "file1.md",
+        1
+    ));
+}
+
+#[test]
+fn test_expr_errors() {
+    let error = |expr: &str| Expr::parse(expr).unwrap_err();
+    assert_eq!(error(""), "empty expression");
+    assert_eq!(
+        error("size >"),
+        "expected a value at the end of the expression"
+    );
+    assert_eq!(
+        error("owner == root"),
+        "expected a field (name, ext, path, type, size, depth or mtime) at column 1, found `owner`"
+    );
+    assert_eq!(
+        error("name < a"),
+        "`<` can't be used with `name` at column 6"
+    );
+    assert_eq!(
+        error("(name == a"),
+        "expected `)` at the end of the expression"
+    );
+    assert_eq!(error("size > 1x"), "invalid size unit `x` at column 8");
+    assert_eq!(error("name == \"a"), "unterminated string at column 9");
+    assert_eq!(
+        error("name == a b"),
+        "expected `and`, `or` or the end of the expression at column 11, found `b`"
+    );
+}
@@ -0,0 +1,434 @@
+use std::path::Path;
+
+use treer::cmd::{
+    root::{Opts, OutputFormat},
+    traversal::print_tree_with_writer,
+};
+
+fn render(path: &str, opts: &Opts) -> String {
+    let mut buffer = Vec::new();
+    let _ = print_tree_with_writer(Path::new(path), opts, &mut buffer);
+    String::from_utf8(buffer).expect("Not valid UTF-8")
+}
+
+#[test]
+fn test_json() {
+    let opts = Opts {
+        output_format: OutputFormat::Json,
+        level: Some(2),
+        ..Default::default()
+    };
+    let expected = r#"[
+  {"type":"directory","name":"tests/sample-directory","contents":[
+    {"type":"directory","name":"sub-dir-lv1","contents":[
+      {"type":"directory","name":"sub-dir-lv2","contents":[
+      ]},
+      {"type":"file","name":"file3.toml"}
+    ]},
+    {"type":"file","name":"file1.md"},
+    {"type":"file","name":"file2.txt"}
+  ]}
+,
+  {"type":"report","directories":2,"files":3}
+]
+"#;
+    assert_eq!(render("tests/sample-directory", &opts), expected);
+}
+
+#[test]
+fn test_json_pattern_and_size() {
+    let opts = Opts {
+        output_format: OutputFormat::Json,
+        patterns: vec![glob::Pattern::new("file[34]*").unwrap()],
+        exclude_patterns: vec![glob::Pattern::new("sub-dir-lv3").unwrap()],
+        print_size: true,
+        ..Default::default()
+    };
+    let expected = r#"[
+  {"type":"directory","name":"tests/sample-directory/sub-dir-lv1","contents":[
+    {"type":"directory","name":"sub-dir-lv2","contents":[
+      {"type":"file","name":"file4","size":30}
+    ]},
+    {"type":"file","name":"file3.toml","size":30}
+  ]}
+,
+  {"type":"report","directories":1,"files":2}
+]
+"#;
+    assert_eq!(
+        render("tests/sample-directory/sub-dir-lv1", &opts),
+        expected
+    );
+}
+
+#[test]
+fn test_json_filelimit() {
+    let opts = Opts {
+        output_format: OutputFormat::Json,
+        filelimit: Some(1),
+        ..Default::default()
+    };
+    let expected = r#"[
+  {"type":"directory","name":"tests/sample-directory","contents":[
+    {"type":"directory","name":"sub-dir-lv1","entries":2,"contents":[
+    ]},
+    {"type":"file","name":"file1.md"},
+    {"type":"file","name":"file2.txt"}
+  ]}
+,
+  {"type":"report","directories":1,"files":2}
+]
+"#;
+    assert_eq!(render("tests/sample-directory", &opts), expected);
+}
+
+#[test]
+fn test_xml_pattern() {
+    let opts = Opts {
+        output_format: OutputFormat::Xml,
+        patterns: vec![glob::Pattern::new("file[34]*").unwrap()],
+        print_size: true,
+        ..Default::default()
+    };
+    let expected = r#"<?xml version="1.0" encoding="UTF-8"?>
+<tree>
+  <directory name="tests/sample-directory">
+    <directory name="sub-dir-lv1">
+      <directory name="sub-dir-lv2">
+        <file name="file4" size="30"/>
+      </directory>
+      <file name="file3.toml" size="30"/>
+    </directory>
+  </directory>
+  <report>
+    <directories>2</directories>
+    <files>2</files>
+  </report>
+</tree>
+"#;
+    assert_eq!(render("tests/sample-directory", &opts), expected);
+}
+
+#[test]
+fn test_html() {
+    let opts = Opts {
+        output_format: OutputFormat::Html,
+        patterns: vec![glob::Pattern::new("*lv2").unwrap()],
+        exclude_patterns: vec![glob::Pattern::new("sub-dir-lv3").unwrap()],
+        highlight: true,
+        print_size: true,
+        base_href: Some(String::from("https://example.com/repo/")),
+        ..Default::default()
+    };
+    let result = render("tests/sample-directory/sub-dir-lv1", &opts);
+    let (head, body) = result.split_once("<body>\n").unwrap();
+    assert!(head.starts_with("<!DOCTYPE html>"));
+    assert!(head.contains("<title>tests/sample-directory/sub-dir-lv1</title>"));
+    let expected = r#"<details class="dir" open><summary><span class="name"><a href="https://example.com/repo">tests/sample-directory/sub-dir-lv1</a></span><span class="size"></span></summary>
+<div class="contents">
+  <details class="dir matched" open><summary><span class="name"><a href="https://example.com/repo/sub-dir-lv2">sub-dir-lv2</a></span><span class="size"></span></summary>
+  <div class="contents in-match">
+    <div class="entry"><span class="name"><a href="https://example.com/repo/sub-dir-lv2/file4">file4</a></span><span class="size">30 B</span></div>
+  </div>
+  </details>
+</div>
+</details>
+<p class="report">1 directory, 1 file</p>
+</body>
+</html>
+"#;
+    assert_eq!(body, expected);
+}
+
+#[test]
+fn test_ndjson() {
+    let opts = Opts {
+        output_format: OutputFormat::Ndjson,
+        patterns: vec![glob::Pattern::new("*lv2").unwrap()],
+        ..Default::default()
+    };
+    let result = render("tests/sample-directory/sub-dir-lv1", &opts);
+    let records: Vec<&str> = result.lines().collect();
+    let expected = [
+        (
+            r#"{"path":"tests/sample-directory/sub-dir-lv1","depth":0,"type":"directory","#,
+            r#""matched":false,"is_last":true}"#,
+        ),
+        (
+            r#"{"path":"tests/sample-directory/sub-dir-lv1/sub-dir-lv2","depth":1,"type":"directory","#,
+            r#""matched":true,"is_last":true}"#,
+        ),
+        (
+            r#"{"path":"tests/sample-directory/sub-dir-lv1/sub-dir-lv2/sub-dir-lv3","depth":2,"type":"directory","#,
+            r#""matched":false,"is_last":false}"#,
+        ),
+        (
+            r#"{"path":"tests/sample-directory/sub-dir-lv1/sub-dir-lv2/sub-dir-lv3/file5.abc","depth":3,"type":"file","size":0,"#,
+            r#""matched":false,"is_last":true}"#,
+        ),
+        (
+            r#"{"path":"tests/sample-directory/sub-dir-lv1/sub-dir-lv2/file4","depth":2,"type":"file","size":30,"#,
+            r#""matched":false,"is_last":true}"#,
+        ),
+    ];
+    assert_eq!(records.len(), expected.len() + 1);
+    for (record, (prefix, suffix)) in records.iter().zip(expected) {
+        assert!(record.starts_with(prefix), "{record}");
+        assert!(record.ends_with(suffix), "{record}");
+    }
+    assert_eq!(
+        records[expected.len()],
+        r#"{"type":"report","directories":2,"files":2}"#
+    );
+}
+
+#[test]
+fn test_ndjson_min_depth() {
+    let opts = Opts {
+        output_format: OutputFormat::Ndjson,
+        min_depth: Some(3),
+        ..Default::default()
+    };
+    let result = render("tests/sample-directory", &opts);
+    let records: Vec<&str> = result.lines().collect();
+    // Depths are relative to the root, not to the first displayed level
+    let expected = [
+        r#"{"path":"tests/sample-directory","depth":0,"#,
+        r#"{"path":"tests/sample-directory/sub-dir-lv1/sub-dir-lv2/sub-dir-lv3","depth":3,"#,
+        r#"{"path":"tests/sample-directory/sub-dir-lv1/sub-dir-lv2/sub-dir-lv3/file5.abc","depth":4,"#,
+        r#"{"path":"tests/sample-directory/sub-dir-lv1/sub-dir-lv2/file4","depth":3,"#,
+    ];
+    assert_eq!(records.len(), expected.len() + 1);
+    for (record, prefix) in records.iter().zip(expected) {
+        assert!(record.starts_with(prefix), "{record}");
+    }
+}
+
+#[test]
+fn test_dot() {
+    let opts = Opts {
+        output_format: OutputFormat::Dot,
+        patterns: vec![glob::Pattern::new("*lv2").unwrap()],
+        exclude_patterns: vec![glob::Pattern::new("sub-dir-lv3").unwrap()],
+        print_size: true,
+        ..Default::default()
+    };
+    let expected = r##"digraph tree {
+  rankdir=LR;
+  node [fontname="monospace"];
+  n0 [label="tests/sample-directory", shape=folder];
+  n1 [label="sub-dir-lv1", shape=folder];
+  n0 -> n1;
+  n2 [label="sub-dir-lv2", shape=folder, style=filled, fillcolor="#f4a3a3"];
+  n1 -> n2;
+  n3 [label="file4\n(30 B)", shape=note];
+  n2 -> n3;
+}
+"##;
+    assert_eq!(render("tests/sample-directory", &opts), expected);
+}
+
+#[test]
+fn test_markdown() {
+    let mut opts = Opts {
+        output_format: OutputFormat::Markdown,
+        patterns: vec![glob::Pattern::new("*lv2").unwrap()],
+        exclude_patterns: vec![glob::Pattern::new("sub-dir-lv3").unwrap()],
+        highlight: true,
+        ..Default::default()
+    };
+    let expected = "- tests/sample-directory/
+  - sub-dir-lv1/
+    - **sub-dir-lv2/**
+      - file4
+";
+    assert_eq!(render("tests/sample-directory", &opts), expected);
+
+    opts.base_href = Some(String::from("."));
+    let expected = "- [tests/sample-directory/](.)
+  - [sub-dir-lv1/](./sub-dir-lv1)
+    - **[sub-dir-lv2/](./sub-dir-lv1/sub-dir-lv2)**
+      - [file4](./sub-dir-lv1/sub-dir-lv2/file4)
+";
+    assert_eq!(render("tests/sample-directory", &opts), expected);
+}
+
+#[test]
+fn test_markdown_list_markers() {
+    let root = std::env::temp_dir().join(format!("treer-markdown-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(&root).unwrap();
+    for name in [
+        "1. intro.md",
+        "2) setup",
+        "- notes",
+        "+ todo",
+        "2024.log",
+        "-rc",
+        "a\nb.md",
+    ] {
+        std::fs::write(root.join(name), "").unwrap();
+    }
+    let mut opts = Opts {
+        output_format: OutputFormat::Markdown,
+        ..Default::default()
+    };
+    let expected = format!(
+        "- {}/
+  - \\+ todo
+  - \\- notes
+  - -rc
+  - 1\\. intro.md
+  - 2\\) setup
+  - 2024.log
+  - a\\nb.md
+",
+        root.display()
+    );
+    assert_eq!(render(root.to_str().unwrap(), &opts), expected);
+
+    // Line breaks in names would also end a Mermaid node
+    opts.output_format = OutputFormat::Mermaid;
+    let result = render(root.to_str().unwrap(), &opts);
+    assert!(result.contains(r#"("a#92;nb.md")"#), "{result}");
+    assert!(!result.lines().any(|line| line.starts_with("b.md")));
+}
+
+#[test]
+fn test_mermaid() {
+    let opts = Opts {
+        output_format: OutputFormat::Mermaid,
+        patterns: vec![glob::Pattern::new("*lv2").unwrap()],
+        exclude_patterns: vec![glob::Pattern::new("sub-dir-lv3").unwrap()],
+        highlight: true,
+        ..Default::default()
+    };
+    let expected = r#"```mermaid
+graph TD
+    n0["tests/sample-directory"]
+    n0 --> n1["sub-dir-lv1"]
+    n1 --> n2["sub-dir-lv2"]
+    n2 --> n3("file4")
+    classDef matched font-weight:bold
+    class n2 matched
+```
+"#;
+    let result = render("tests/sample-directory", &opts);
+    assert!(!result.contains('\u{1b}'));
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_csv_and_tsv() {
+    let mut opts = Opts {
+        output_format: OutputFormat::Csv,
+        patterns: vec![glob::Pattern::new("*lv2").unwrap()],
+        exclude_patterns: vec![glob::Pattern::new("sub-dir-lv3").unwrap()],
+        ..Default::default()
+    };
+    let result = render("tests/sample-directory", &opts);
+    let rows: Vec<Vec<&str>> = result.lines().map(|row| row.split(',').collect()).collect();
+    assert_eq!(
+        rows[0],
+        ["path", "depth", "type", "size", "mtime", "mode", "matched"]
+    );
+    assert_eq!(rows.len(), 4);
+    assert_eq!(
+        [rows[1][0], rows[1][1], rows[1][2], rows[1][6]],
+        ["sub-dir-lv1", "1", "directory", "false"]
+    );
+    assert_eq!(
+        [rows[2][0], rows[2][1], rows[2][2], rows[2][6]],
+        ["sub-dir-lv1/sub-dir-lv2", "2", "directory", "true"]
+    );
+    assert_eq!(
+        [rows[3][0], rows[3][1], rows[3][2], rows[3][3], rows[3][6]],
+        ["sub-dir-lv1/sub-dir-lv2/file4", "3", "file", "30", "false"]
+    );
+    assert!(rows[3][4].ends_with('Z') && rows[3][4].contains('T'));
+
+    opts.output_format = OutputFormat::Tsv;
+    let result = render("tests/sample-directory", &opts);
+    assert!(result.starts_with("path\tdepth\ttype\tsize\tmtime\tmode\tmatched\n"));
+    assert!(result.contains("sub-dir-lv1/sub-dir-lv2/file4\t3\tfile\t30\t"));
+}
+
+#[test]
+fn test_print0() {
+    let opts = Opts {
+        output_format: OutputFormat::Print0,
+        patterns: vec![glob::Pattern::new("file[34]*").unwrap()],
+        ..Default::default()
+    };
+    let expected = "tests/sample-directory/sub-dir-lv1\0\
+tests/sample-directory/sub-dir-lv1/sub-dir-lv2\0\
+tests/sample-directory/sub-dir-lv1/sub-dir-lv2/file4\0\
+tests/sample-directory/sub-dir-lv1/file3.toml\0";
+    assert_eq!(render("tests/sample-directory", &opts), expected);
+}
+
+#[test]
+fn test_svg() {
+    let opts = Opts {
+        output_format: OutputFormat::Svg,
+        patterns: vec![glob::Pattern::new("*lv2").unwrap()],
+        exclude_patterns: vec![glob::Pattern::new("sub-dir-lv3").unwrap()],
+        highlight: true,
+        ..Default::default()
+    };
+    let expected = r##"<svg xmlns="http://www.w3.org/2000/svg" width="201" height="132" viewBox="0 0 201 132">
+<rect width="100%" height="100%" fill="#1e1e1e"/>
+<g font-family="monospace" font-size="14" fill="#d4d4d4" xml:space="preserve">
+<text x="12" y="26">sample-directory</text>
+<text x="12" y="44">└── sub-dir-lv1</text>
+<text x="12" y="62">    └── <tspan fill="#f14c4c" font-weight="bold">sub-dir-lv2</tspan></text>
+<text x="12" y="80">        <tspan fill="#f14c4c">└── </tspan>file4</text>
+<text x="12" y="116">2 directories, 1 file</text>
+</g>
+</svg>
+"##;
+    let result = render("tests/sample-directory", &opts);
+    assert_eq!(result, expected);
+    assert_eq!(render("tests/sample-directory", &opts), result);
+}
+
+#[test]
+fn test_yaml() {
+    let mut opts = Opts {
+        output_format: OutputFormat::Yaml,
+        level: Some(2),
+        dir_only: true,
+        ..Default::default()
+    };
+    let expected = r#""tests/sample-directory":
+  type: directory
+  contents:
+    "sub-dir-lv1":
+      type: directory
+      contents:
+        "sub-dir-lv2":
+          type: directory
+          contents: {}
+"#;
+    assert_eq!(render("tests/sample-directory", &opts), expected);
+
+    opts.level = None;
+    opts.dir_only = false;
+    opts.print_size = true;
+    opts.patterns = vec![glob::Pattern::new("*lv2").unwrap()];
+    opts.exclude_patterns = vec![glob::Pattern::new("sub-dir-lv3").unwrap()];
+    let expected = r#""tests/sample-directory":
+  type: directory
+  contents:
+    "sub-dir-lv1":
+      type: directory
+      contents:
+        "sub-dir-lv2":
+          type: directory
+          contents:
+            "file4":
+              type: file
+              size: 30
+"#;
+    assert_eq!(render("tests/sample-directory", &opts), expected);
+}